@@ -17,11 +17,15 @@ use solana_program::{
 pub mod escrow;
 pub mod multisig;
 pub mod recurring;
+pub mod streaming;
+pub mod subscription;
 pub mod yield_farming;
 
 use escrow::*;
 use multisig::*;
 use recurring::*;
+use streaming::*;
+use subscription::*;
 use yield_farming::*;
 
 entrypoint!(process_instruction);
@@ -84,6 +88,18 @@ pub fn process_instruction(
             msg!("Instruction: Reject Transaction");
             process_reject_transaction(program_id, accounts, data)
         }
+        [0x2e, 0x6b, 0x9d, 0x4a, 0x8f, 0x3c, 0x7e, 0x51] => {
+            msg!("Instruction: Execute Transaction With Signatures");
+            process_execute_with_signatures(program_id, accounts, data)
+        }
+        [0x61, 0x1f, 0xc4, 0x8e, 0x3a, 0x97, 0x5b, 0x26] => {
+            msg!("Instruction: Propose Batch");
+            process_propose_batch(program_id, accounts, data)
+        }
+        [0xa3, 0x58, 0x2d, 0xe7, 0x49, 0x1b, 0xc6, 0x74] => {
+            msg!("Instruction: Execute Batch");
+            process_execute_batch(program_id, accounts, data)
+        }
 
         // Yield Farming instructions
         [0x95, 0xc0, 0xa0, 0xfe, 0xf8, 0x6c, 0x5c, 0x9d] => {
@@ -106,6 +122,66 @@ pub fn process_instruction(
             msg!("Instruction: Compound Rewards");
             process_compound_rewards(program_id, accounts, data)
         }
+        [0x4a, 0xd5, 0x2c, 0x8e, 0x17, 0xf0, 0x63, 0xb9] => {
+            msg!("Instruction: Fund Rewards");
+            process_fund_rewards(program_id, accounts, data)
+        }
+        [0xb6, 0x0e, 0x53, 0xf9, 0x2d, 0x84, 0x7a, 0xc1] => {
+            msg!("Instruction: Update Reward Rate");
+            process_update_reward_rate(program_id, accounts, data)
+        }
+        [0x27, 0xca, 0x94, 0x1b, 0x6e, 0xd3, 0x05, 0x8f] => {
+            msg!("Instruction: Set Pool Paused");
+            process_set_pool_paused(program_id, accounts, data)
+        }
+        [0x8d, 0x31, 0xe7, 0x5c, 0xa0, 0x49, 0xbe, 0x16] => {
+            msg!("Instruction: Set Max Total Staked");
+            process_set_max_total_staked(program_id, accounts, data)
+        }
+        [0x50, 0xfb, 0x38, 0xd6, 0x0c, 0x97, 0x2a, 0xe4] => {
+            msg!("Instruction: Transfer Pool Authority");
+            process_transfer_pool_authority(program_id, accounts, data)
+        }
+        [0xe2, 0x6c, 0x0f, 0x87, 0xb4, 0x1d, 0x59, 0x3a] => {
+            msg!("Instruction: Accept Pool Authority");
+            process_accept_pool_authority(program_id, accounts, data)
+        }
+        [0x13, 0x9e, 0xa7, 0x4b, 0xf5, 0x60, 0xd8, 0x2c] => {
+            msg!("Instruction: Close Staking Pool");
+            process_close_staking_pool(program_id, accounts, data)
+        }
+        [0xa9, 0x35, 0x6b, 0xd0, 0x1e, 0x82, 0xf4, 0x47] => {
+            msg!("Instruction: Early Unstake");
+            process_early_unstake(program_id, accounts, data)
+        }
+        [0x5e, 0x0d, 0xb1, 0x79, 0xc6, 0x2f, 0x93, 0x68] => {
+            msg!("Instruction: Set Early Unstake Penalty");
+            process_set_early_unstake_penalty(program_id, accounts, data)
+        }
+        [0x36, 0xf8, 0x1a, 0xc5, 0x7d, 0x02, 0xbe, 0x91] => {
+            msg!("Instruction: Request Unstake");
+            process_request_unstake(program_id, accounts, data)
+        }
+        [0xcb, 0x47, 0x8e, 0x23, 0x5a, 0xf6, 0x10, 0x7d] => {
+            msg!("Instruction: Withdraw Unstaked");
+            process_withdraw_unstaked(program_id, accounts, data)
+        }
+        [0x72, 0xad, 0x64, 0x0e, 0x98, 0x3b, 0xd7, 0x55] => {
+            msg!("Instruction: Set Unstake Cooldown");
+            process_set_unstake_cooldown(program_id, accounts, data)
+        }
+        [0x9a, 0x14, 0xe6, 0x3f, 0x52, 0xcb, 0x0d, 0x87] => {
+            msg!("Instruction: Set Tier Table");
+            process_set_tier_table(program_id, accounts, data)
+        }
+        [0x2e, 0xb8, 0x71, 0xc4, 0x09, 0x6d, 0xf3, 0x1a] => {
+            msg!("Instruction: Set Lock Table");
+            process_set_lock_table(program_id, accounts, data)
+        }
+        [0x6f, 0xd2, 0x38, 0xa5, 0x1c, 0xe9, 0x74, 0x0b] => {
+            msg!("Instruction: Extend Lock");
+            process_extend_lock(program_id, accounts, data)
+        }
 
         // Recurring Payments instructions
         [0x6a, 0x4e, 0x8f, 0x3d, 0x9c, 0x7b, 0x2a, 0x5f] => {
@@ -120,6 +196,70 @@ pub fn process_instruction(
             msg!("Instruction: Cancel Recurring Payment");
             process_cancel_recurring_payment(program_id, accounts, data)
         }
+        [0x9d, 0x3e, 0x71, 0xc2, 0x58, 0xaf, 0x06, 0x4b] => {
+            msg!("Instruction: Pause Recurring Payment");
+            process_pause_recurring_payment(program_id, accounts, data)
+        }
+        [0x4f, 0xb8, 0x26, 0x93, 0xe1, 0x7c, 0x5a, 0xd0] => {
+            msg!("Instruction: Resume Recurring Payment");
+            process_resume_recurring_payment(program_id, accounts, data)
+        }
+        [0xe5, 0x12, 0x8a, 0x3b, 0x6f, 0xd4, 0x97, 0x2c] => {
+            msg!("Instruction: Amend Recurring Payment");
+            process_amend_recurring_payment(program_id, accounts, data)
+        }
+        [0x0a, 0x7d, 0xc3, 0x95, 0x2f, 0xe6, 0x41, 0xb8] => {
+            msg!("Instruction: Accept Recurring Payment");
+            process_accept_recurring_payment(program_id, accounts, data)
+        }
+        [0xd1, 0x46, 0xaf, 0x08, 0x7b, 0x32, 0xec, 0x59] => {
+            msg!("Instruction: Terminate Recurring Payment");
+            process_terminate_recurring_payment(program_id, accounts, data)
+        }
+        [0x63, 0xe9, 0x14, 0xbd, 0x86, 0x0c, 0x5a, 0xf7] => {
+            msg!("Instruction: Redirect Recurring Payment");
+            process_redirect_recurring_payment(program_id, accounts, data)
+        }
+
+        // Streaming Payments instructions
+        [0x1d, 0x84, 0xe2, 0x5a, 0xc7, 0x39, 0x6f, 0xb3] => {
+            msg!("Instruction: Create Stream");
+            process_create_stream(program_id, accounts, data)
+        }
+        [0x58, 0xa6, 0x0b, 0xf1, 0x93, 0x2e, 0xd4, 0x7c] => {
+            msg!("Instruction: Withdraw From Stream");
+            process_withdraw_from_stream(program_id, accounts, data)
+        }
+        [0xb2, 0x4d, 0x97, 0x16, 0xe8, 0x5c, 0x03, 0xaf] => {
+            msg!("Instruction: Top Up Stream");
+            process_top_up_stream(program_id, accounts, data)
+        }
+        [0x7e, 0xc1, 0x3a, 0x64, 0x0f, 0xb9, 0x82, 0xd5] => {
+            msg!("Instruction: Cancel Stream");
+            process_cancel_stream(program_id, accounts, data)
+        }
+
+        // Subscription instructions
+        [0x3c, 0x91, 0x5e, 0xa8, 0x27, 0xd6, 0x4b, 0x0f] => {
+            msg!("Instruction: Create Subscription Plan");
+            process_create_subscription_plan(program_id, accounts, data)
+        }
+        [0xc4, 0x2a, 0x7f, 0x18, 0xb5, 0x63, 0xe9, 0x50] => {
+            msg!("Instruction: Update Plan Price");
+            process_update_plan_price(program_id, accounts, data)
+        }
+        [0x85, 0xf3, 0x0c, 0x6d, 0x4e, 0xa1, 0x97, 0x2b] => {
+            msg!("Instruction: Subscribe");
+            process_subscribe(program_id, accounts, data)
+        }
+        [0x19, 0xb7, 0xd2, 0x40, 0x8c, 0x5f, 0x6a, 0xe3] => {
+            msg!("Instruction: Charge Subscription");
+            process_charge_subscription(program_id, accounts, data)
+        }
+        [0xe8, 0x06, 0x9b, 0x72, 0x31, 0xcd, 0x58, 0xa4] => {
+            msg!("Instruction: Unsubscribe");
+            process_unsubscribe(program_id, accounts, data)
+        }
 
         _ => {
             msg!("Unknown instruction discriminator: {:?}", discriminator);
@@ -2,21 +2,31 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     clock::Clock,
+    ed25519_program,
     entrypoint::ProgramResult,
+    hash::hashv,
     msg,
     program::{invoke, invoke_signed},
     program_error::ProgramError,
     pubkey::Pubkey,
     rent::Rent,
     system_instruction,
-    sysvar::Sysvar,
+    sysvar::{
+        instructions::{load_current_index_checked, load_instruction_at_checked},
+        Sysvar,
+    },
 };
+use std::convert::TryFrom;
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct Multisig {
     pub creator: Pubkey,
     pub owners: Vec<Pubkey>,
-    pub threshold: u8,
+    pub permissions: Vec<u8>, // Permission bits, one entry per owner
+    pub weights: Vec<u8>,     // Voting weight, one entry per owner
+    pub threshold: u16,       // Total approval weight required
+    pub open_execution: bool, // Anyone may execute once the threshold is met
+    pub vault_count: u8,      // Vault PDAs 0..vault_count may hold funds
     pub transaction_count: u64,
     pub created_at: i64,
     pub bump: u8,
@@ -24,7 +34,66 @@ pub struct Multisig {
 
 impl Multisig {
     pub const MAX_OWNERS: usize = 10;
-    pub const MAX_SIZE: usize = 32 + 4 + (32 * Self::MAX_OWNERS) + 1 + 8 + 8 + 1; // ~410 bytes
+    pub const MAX_VAULTS: u8 = 8;
+    pub const MAX_SIZE: usize = 32
+        + 4 + (32 * Self::MAX_OWNERS) // owners
+        + 4 + Self::MAX_OWNERS // permissions
+        + 4 + Self::MAX_OWNERS // weights
+        + 2 + 1 + 1 + 8 + 8 + 1; // ~405 bytes
+
+    pub fn vault_address(
+        multisig: &Pubkey,
+        vault_index: u8,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"vault", multisig.as_ref(), &[vault_index]], program_id)
+    }
+
+    pub fn has_permission(&self, member: &Pubkey, permission: u8) -> bool {
+        self.owners
+            .iter()
+            .position(|owner| owner == member)
+            .map(|index| self.permissions[index] & permission == permission)
+            .unwrap_or(false)
+    }
+
+    pub fn can_execute(&self, executor: &Pubkey) -> bool {
+        self.open_execution || self.has_permission(executor, Permissions::EXECUTE)
+    }
+
+    // Zero for non-members and members without vote permission
+    pub fn weight_of(&self, member: &Pubkey) -> u16 {
+        match self.owners.iter().position(|owner| owner == member) {
+            Some(index) if self.permissions[index] & Permissions::VOTE != 0 => {
+                self.weights[index] as u16
+            }
+            _ => 0,
+        }
+    }
+
+    pub fn tally(&self, voters: &[Pubkey]) -> u16 {
+        voters.iter().map(|voter| self.weight_of(voter)).sum()
+    }
+
+    pub fn total_voting_weight(&self) -> u16 {
+        self.tally(&self.owners)
+    }
+
+    // Rejected once the remaining weight can no longer reach the threshold
+    pub fn is_rejected(&self, transaction: &MultisigTransaction) -> bool {
+        self.tally(&transaction.rejections)
+            > self.total_voting_weight().saturating_sub(self.threshold)
+    }
+}
+
+// Permission bits stored in Multisig::permissions
+pub struct Permissions;
+
+impl Permissions {
+    pub const PROPOSE: u8 = 1 << 0;
+    pub const VOTE: u8 = 1 << 1;
+    pub const EXECUTE: u8 = 1 << 2;
+    pub const ALL: u8 = Self::PROPOSE | Self::VOTE | Self::EXECUTE;
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
@@ -32,8 +101,11 @@ pub struct MultisigTransaction {
     pub multisig: Pubkey,
     pub recipient: Pubkey,
     pub amount: u64,
+    pub vault_index: u8,
+    pub batch_size: u16, // Number of transfers in the linked `MultisigBatch`, 0 for a single transfer
     pub transaction_index: u64,
     pub approvals: Vec<Pubkey>,
+    pub rejections: Vec<Pubkey>,
     pub executed: bool,
     pub proposer: Pubkey,
     pub created_at: i64,
@@ -42,19 +114,76 @@ pub struct MultisigTransaction {
 }
 
 impl MultisigTransaction {
-    pub const MAX_SIZE: usize = 32 + 32 + 8 + 8 + 4 + (32 * 10) + 1 + 32 + 8 + 4 + 100 + 1; // ~550 bytes
+    pub const APPROVAL_DOMAIN: &'static [u8] = b"pyusd-manifesto:multisig-approve";
+    pub const MAX_SIZE: usize =
+        32 + 32 + 8 + 1 + 2 + 8 + 4 + (32 * 10) + 4 + (32 * 10) + 1 + 32 + 8 + 4 + 100 + 1; // ~875 bytes
+
+    // Digest owners sign off-chain to approve this proposal
+    pub fn approval_digest(&self, transaction_key: &Pubkey) -> [u8; 32] {
+        hashv(&[
+            Self::APPROVAL_DOMAIN,
+            self.multisig.as_ref(),
+            transaction_key.as_ref(),
+            &self.transaction_index.to_le_bytes(),
+            self.recipient.as_ref(),
+            &self.amount.to_le_bytes(),
+            &[self.vault_index],
+            &self.batch_size.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct BatchTransfer {
+    pub recipient_token_account: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct MultisigBatch {
+    pub transaction: Pubkey,
+    pub transfers: Vec<BatchTransfer>,
+    pub executed_count: u16, // Transfers already paid, execution resumes from here
+    pub bump: u8,
+}
+
+impl MultisigBatch {
+    pub const MAX_TRANSFERS: usize = 25;
+    pub const MAX_SIZE: usize = 32 + 4 + (40 * Self::MAX_TRANSFERS) + 2 + 1; // ~1040 bytes
+
+    pub fn is_complete(&self) -> bool {
+        self.executed_count as usize >= self.transfers.len()
+    }
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct CreateMultisigArgs {
     pub owners: Vec<Pubkey>,
-    pub threshold: u8,
+    pub permissions: Vec<u8>, // Empty grants every owner all permissions
+    pub weights: Vec<u8>,     // Empty gives every owner a weight of 1
+    pub threshold: u16,
+    pub open_execution: bool,
+    pub vault_count: u8,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ProposeBatchArgs {
+    pub vault_index: u8,
+    pub transfers: Vec<BatchTransfer>,
+    pub description: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ExecuteBatchArgs {
+    pub max_transfers: u16, // Chunk size; pass the batch size to execute atomically
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct ProposeTransactionArgs {
     pub amount: u64,
     pub recipient: Pubkey,
+    pub vault_index: u8,
     pub description: String,
 }
 
@@ -83,11 +212,74 @@ pub fn process_create_multisig(
         return Err(ProgramError::InvalidArgument);
     }
 
-    if args.threshold == 0 || args.threshold as usize > args.owners.len() {
+    // Permissions and weights are looked up by an owner's first entry
+    if args
+        .owners
+        .iter()
+        .enumerate()
+        .any(|(index, owner)| args.owners[..index].contains(owner))
+    {
+        msg!("Duplicate owners are not allowed");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let permissions = if args.permissions.is_empty() {
+        vec![Permissions::ALL; args.owners.len()]
+    } else {
+        args.permissions
+    };
+
+    if permissions.len() != args.owners.len() {
+        msg!("Permissions must be provided for every owner");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if permissions
+        .iter()
+        .any(|bits| *bits == 0 || bits & !Permissions::ALL != 0)
+    {
+        msg!("Invalid permission bits");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let weights = if args.weights.is_empty() {
+        vec![1; args.owners.len()]
+    } else {
+        args.weights
+    };
+
+    if weights.len() != args.owners.len() {
+        msg!("Weights must be provided for every owner");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if weights.contains(&0) {
+        msg!("Owner weights must be greater than 0");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let voting_weight: u16 = permissions
+        .iter()
+        .zip(weights.iter())
+        .filter(|(bits, _)| *bits & Permissions::VOTE != 0)
+        .map(|(_, weight)| *weight as u16)
+        .sum();
+
+    if args.threshold == 0 || args.threshold > voting_weight {
         msg!("Invalid threshold");
         return Err(ProgramError::InvalidArgument);
     }
 
+    if !args.open_execution && !permissions.iter().any(|bits| bits & Permissions::EXECUTE != 0) {
+        msg!("At least one owner must be able to execute");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if args.vault_count == 0 || args.vault_count > Multisig::MAX_VAULTS {
+        msg!("Invalid number of vaults");
+        return Err(ProgramError::InvalidArgument);
+    }
+
     let clock = Clock::get()?;
     let current_time = clock.unix_timestamp;
 
@@ -132,7 +324,11 @@ pub fn process_create_multisig(
     let multisig_data = Multisig {
         creator: *creator.key,
         owners: args.owners,
+        permissions,
+        weights,
         threshold: args.threshold,
+        open_execution: args.open_execution,
+        vault_count: args.vault_count,
         transaction_count: 0,
         created_at: current_time,
         bump,
@@ -170,9 +366,152 @@ pub fn process_propose_transaction(
     let mut multisig_data = Multisig::try_from_slice(&multisig_account.data.borrow())
         .map_err(|_| ProgramError::InvalidAccountData)?;
 
-    // Verify proposer is an owner
-    if !multisig_data.owners.contains(proposer.key) {
-        msg!("Proposer is not an owner");
+    if args.vault_index >= multisig_data.vault_count {
+        msg!("Invalid vault index");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut transaction_data = create_transaction_account(
+        program_id,
+        multisig_account,
+        &mut multisig_data,
+        transaction_account,
+        proposer,
+        system_program,
+    )?;
+
+    transaction_data.recipient = args.recipient;
+    transaction_data.amount = args.amount;
+    transaction_data.vault_index = args.vault_index;
+    transaction_data.description = args.description;
+    transaction_data.serialize(&mut &mut transaction_account.data.borrow_mut()[..])?;
+
+    msg!("Transaction proposed successfully!");
+    msg!("Transaction index: {}", transaction_data.transaction_index);
+
+    Ok(())
+}
+
+// Transfers from one vault approved as a single proposal
+pub fn process_propose_batch(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let multisig_account = next_account_info(accounts_iter)?;
+    let transaction_account = next_account_info(accounts_iter)?;
+    let batch_account = next_account_info(accounts_iter)?;
+    let proposer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !proposer.is_signer {
+        msg!("Proposer must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let args = ProposeBatchArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    if args.transfers.is_empty() || args.transfers.len() > MultisigBatch::MAX_TRANSFERS {
+        msg!("Invalid number of transfers");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut total_amount: u64 = 0;
+    for transfer in &args.transfers {
+        if transfer.amount == 0 {
+            msg!("Transfer amount must be greater than 0");
+            return Err(ProgramError::InvalidArgument);
+        }
+        total_amount = total_amount
+            .checked_add(transfer.amount)
+            .ok_or(ProgramError::InvalidArgument)?;
+    }
+
+    // Load multisig
+    let mut multisig_data = Multisig::try_from_slice(&multisig_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if args.vault_index >= multisig_data.vault_count {
+        msg!("Invalid vault index");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut transaction_data = create_transaction_account(
+        program_id,
+        multisig_account,
+        &mut multisig_data,
+        transaction_account,
+        proposer,
+        system_program,
+    )?;
+
+    // Derive batch PDA
+    let (batch_pda, bump) = Pubkey::find_program_address(
+        &[b"batch", transaction_account.key.as_ref()],
+        program_id,
+    );
+
+    if batch_pda != *batch_account.key {
+        msg!("Invalid batch PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Create batch account
+    let rent = Rent::get()?;
+    let space = MultisigBatch::MAX_SIZE;
+    let lamports = rent.minimum_balance(space);
+
+    invoke_signed(
+        &system_instruction::create_account(
+            proposer.key,
+            batch_account.key,
+            lamports,
+            space as u64,
+            program_id,
+        ),
+        &[proposer.clone(), batch_account.clone(), system_program.clone()],
+        &[&[b"batch", transaction_account.key.as_ref(), &[bump]]],
+    )?;
+
+    let batch_data = MultisigBatch {
+        transaction: *transaction_account.key,
+        transfers: args.transfers,
+        executed_count: 0,
+        bump,
+    };
+
+    batch_data.serialize(&mut &mut batch_account.data.borrow_mut()[..])?;
+
+    // The transaction approves the batch as a whole
+    transaction_data.recipient = *batch_account.key;
+    transaction_data.amount = total_amount;
+    transaction_data.vault_index = args.vault_index;
+    transaction_data.batch_size = batch_data.transfers.len() as u16;
+    transaction_data.description = args.description;
+    transaction_data.serialize(&mut &mut transaction_account.data.borrow_mut()[..])?;
+
+    msg!("Batch proposed successfully!");
+    msg!("Transaction index: {}", transaction_data.transaction_index);
+    msg!("Transfers: {}, total amount: {}", transaction_data.batch_size, total_amount);
+
+    Ok(())
+}
+
+// The caller fills in the proposal details
+fn create_transaction_account<'a>(
+    program_id: &Pubkey,
+    multisig_account: &AccountInfo<'a>,
+    multisig_data: &mut Multisig,
+    transaction_account: &AccountInfo<'a>,
+    proposer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> Result<MultisigTransaction, ProgramError> {
+    // Verify proposer is allowed to propose
+    if !multisig_data.has_permission(proposer.key, Permissions::PROPOSE) {
+        msg!("Proposer does not have propose permission");
         return Err(ProgramError::InvalidAccountData);
     }
 
@@ -216,30 +555,32 @@ pub fn process_propose_transaction(
         ]],
     )?;
 
-    // Initialize transaction data
-    let transaction_data = MultisigTransaction {
-        multisig: *multisig_account.key,
-        recipient: args.recipient,
-        amount: args.amount,
-        transaction_index,
-        approvals: vec![*proposer.key], // Proposer auto-approves
-        executed: false,
-        proposer: *proposer.key,
-        created_at: clock.unix_timestamp,
-        description: args.description,
-        bump,
+    // Proposer auto-approves only if they are allowed to vote
+    let approvals = if multisig_data.has_permission(proposer.key, Permissions::VOTE) {
+        vec![*proposer.key]
+    } else {
+        vec![]
     };
 
-    transaction_data.serialize(&mut &mut transaction_account.data.borrow_mut()[..])?;
-
     // Increment transaction count
     multisig_data.transaction_count += 1;
     multisig_data.serialize(&mut &mut multisig_account.data.borrow_mut()[..])?;
 
-    msg!("Transaction proposed successfully!");
-    msg!("Transaction index: {}", transaction_index);
-
-    Ok(())
+    Ok(MultisigTransaction {
+        multisig: *multisig_account.key,
+        recipient: Pubkey::default(),
+        amount: 0,
+        vault_index: 0,
+        batch_size: 0,
+        transaction_index,
+        approvals,
+        rejections: vec![],
+        executed: false,
+        proposer: *proposer.key,
+        created_at: clock.unix_timestamp,
+        description: String::new(),
+        bump,
+    })
 }
 
 pub fn process_approve_transaction(
@@ -258,13 +599,18 @@ pub fn process_approve_transaction(
         return Err(ProgramError::MissingRequiredSignature);
     }
 
+    if multisig_account.owner != program_id || transaction_account.owner != program_id {
+        msg!("Multisig accounts must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
     // Load multisig
     let multisig_data = Multisig::try_from_slice(&multisig_account.data.borrow())
         .map_err(|_| ProgramError::InvalidAccountData)?;
 
-    // Verify approver is an owner
-    if !multisig_data.owners.contains(approver.key) {
-        msg!("Approver is not an owner");
+    // Verify approver is allowed to vote
+    if !multisig_data.has_permission(approver.key, Permissions::VOTE) {
+        msg!("Approver does not have vote permission");
         return Err(ProgramError::InvalidAccountData);
     }
 
@@ -272,14 +618,26 @@ pub fn process_approve_transaction(
     let mut transaction_data = MultisigTransaction::try_from_slice(&transaction_account.data.borrow())
         .map_err(|_| ProgramError::InvalidAccountData)?;
 
+    if transaction_data.multisig != *multisig_account.key {
+        msg!("Transaction does not belong to this multisig");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
     if transaction_data.executed {
         msg!("Transaction already executed");
         return Err(ProgramError::InvalidAccountData);
     }
 
-    // Check if already approved
-    if transaction_data.approvals.contains(approver.key) {
-        msg!("Already approved by this owner");
+    if multisig_data.is_rejected(&transaction_data) {
+        msg!("Transaction has been rejected");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Check if already voted
+    if transaction_data.approvals.contains(approver.key)
+        || transaction_data.rejections.contains(approver.key)
+    {
+        msg!("Already voted by this owner");
         return Err(ProgramError::InvalidAccountData);
     }
 
@@ -288,7 +646,11 @@ pub fn process_approve_transaction(
     transaction_data.serialize(&mut &mut transaction_account.data.borrow_mut()[..])?;
 
     msg!("Transaction approved!");
-    msg!("Total approvals: {}", transaction_data.approvals.len());
+    msg!(
+        "Approval weight: {}/{}",
+        multisig_data.tally(&transaction_data.approvals),
+        multisig_data.threshold
+    );
 
     Ok(())
 }
@@ -302,7 +664,8 @@ pub fn process_execute_transaction(
 
     let multisig_account = next_account_info(accounts_iter)?;
     let transaction_account = next_account_info(accounts_iter)?;
-    let multisig_token_account = next_account_info(accounts_iter)?;
+    let vault_account = next_account_info(accounts_iter)?;
+    let vault_token_account = next_account_info(accounts_iter)?;
     let recipient_token_account = next_account_info(accounts_iter)?;
     let executor = next_account_info(accounts_iter)?;
     let token_program = next_account_info(accounts_iter)?;
@@ -312,52 +675,55 @@ pub fn process_execute_transaction(
         return Err(ProgramError::MissingRequiredSignature);
     }
 
+    if multisig_account.owner != program_id || transaction_account.owner != program_id {
+        msg!("Multisig accounts must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
     // Load multisig
     let multisig_data = Multisig::try_from_slice(&multisig_account.data.borrow())
         .map_err(|_| ProgramError::InvalidAccountData)?;
 
+    // Verify executor is allowed to execute
+    if !multisig_data.can_execute(executor.key) {
+        msg!("Executor does not have execute permission");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
     // Load transaction
     let mut transaction_data = MultisigTransaction::try_from_slice(&transaction_account.data.borrow())
         .map_err(|_| ProgramError::InvalidAccountData)?;
 
+    if transaction_data.multisig != *multisig_account.key {
+        msg!("Transaction does not belong to this multisig");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
     if transaction_data.executed {
         msg!("Transaction already executed");
         return Err(ProgramError::InvalidAccountData);
     }
 
+    if transaction_data.batch_size > 0 {
+        msg!("Batch transactions must be executed with execute batch");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
     // Check if threshold met
-    if transaction_data.approvals.len() < multisig_data.threshold as usize {
+    if multisig_data.tally(&transaction_data.approvals) < multisig_data.threshold {
         msg!("Threshold not met");
         return Err(ProgramError::InvalidAccountData);
     }
 
     // Execute transfer
-    let transfer_instruction = spl_token_2022::instruction::transfer_checked(
-        token_program.key,
-        multisig_token_account.key,
-        &spl_token_2022::id(),
-        recipient_token_account.key,
-        multisig_account.key, // PDA authority
-        &[],
-        transaction_data.amount,
-        6,
-    )?;
-
-    invoke_signed(
-        &transfer_instruction,
-        &[
-            multisig_token_account.clone(),
-            recipient_token_account.clone(),
-            multisig_account.clone(),
-            token_program.clone(),
-        ],
-        &[&[
-            b"multisig",
-            multisig_data.creator.as_ref(),
-            &multisig_data.created_at.to_le_bytes(),
-            &[multisig_data.bump],
-        ]],
+    let vault = Vault::load(
+        program_id,
+        multisig_account,
+        vault_account,
+        vault_token_account,
+        transaction_data.vault_index,
     )?;
+    vault.transfer(recipient_token_account, token_program, transaction_data.amount)?;
 
     // Mark as executed
     transaction_data.executed = true;
@@ -368,6 +734,364 @@ pub fn process_execute_transaction(
     Ok(())
 }
 
+// Signatures are verified by Ed25519 instructions earlier in the transaction
+pub fn process_execute_with_signatures(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let multisig_account = next_account_info(accounts_iter)?;
+    let transaction_account = next_account_info(accounts_iter)?;
+    let vault_account = next_account_info(accounts_iter)?;
+    let vault_token_account = next_account_info(accounts_iter)?;
+    let recipient_token_account = next_account_info(accounts_iter)?;
+    let executor = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let instructions_sysvar = next_account_info(accounts_iter)?;
+
+    if !executor.is_signer {
+        msg!("Executor must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if multisig_account.owner != program_id || transaction_account.owner != program_id {
+        msg!("Multisig accounts must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Load multisig
+    let multisig_data = Multisig::try_from_slice(&multisig_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    // Verify executor is allowed to execute
+    if !multisig_data.can_execute(executor.key) {
+        msg!("Executor does not have execute permission");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Load transaction
+    let mut transaction_data = MultisigTransaction::try_from_slice(&transaction_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if transaction_data.multisig != *multisig_account.key {
+        msg!("Transaction does not belong to this multisig");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if transaction_data.executed {
+        msg!("Transaction already executed");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if transaction_data.batch_size > 0 {
+        msg!("Batch transactions must be executed with execute batch");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Collect owners who signed the proposal digest off-chain
+    let digest = transaction_data.approval_digest(transaction_account.key);
+    let signers = load_ed25519_signers(instructions_sysvar, &digest)?;
+
+    for signer in signers {
+        if multisig_data.weight_of(&signer) == 0 {
+            msg!("Ignoring signature from non-voter: {}", signer);
+            continue;
+        }
+
+        if !transaction_data.approvals.contains(&signer)
+            && !transaction_data.rejections.contains(&signer)
+        {
+            transaction_data.approvals.push(signer);
+        }
+    }
+
+    // Check if threshold met
+    let approval_weight = multisig_data.tally(&transaction_data.approvals);
+    if approval_weight < multisig_data.threshold {
+        msg!("Threshold not met");
+        msg!("Approval weight: {}/{}", approval_weight, multisig_data.threshold);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Execute transfer
+    let vault = Vault::load(
+        program_id,
+        multisig_account,
+        vault_account,
+        vault_token_account,
+        transaction_data.vault_index,
+    )?;
+    vault.transfer(recipient_token_account, token_program, transaction_data.amount)?;
+
+    // Record approvals and mark as executed
+    transaction_data.executed = true;
+    transaction_data.serialize(&mut &mut transaction_account.data.borrow_mut()[..])?;
+
+    msg!("Transaction executed with off-chain signatures!");
+    msg!("Approval weight: {}/{}", approval_weight, multisig_data.threshold);
+
+    Ok(())
+}
+
+// Recipient token accounts are trailing accounts in batch order
+pub fn process_execute_batch(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let multisig_account = next_account_info(accounts_iter)?;
+    let transaction_account = next_account_info(accounts_iter)?;
+    let batch_account = next_account_info(accounts_iter)?;
+    let vault_account = next_account_info(accounts_iter)?;
+    let vault_token_account = next_account_info(accounts_iter)?;
+    let executor = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if !executor.is_signer {
+        msg!("Executor must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let args = ExecuteBatchArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    if batch_account.owner != program_id || transaction_account.owner != program_id {
+        msg!("Multisig accounts must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Load multisig
+    let multisig_data = Multisig::try_from_slice(&multisig_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    // Verify executor is allowed to execute
+    if !multisig_data.can_execute(executor.key) {
+        msg!("Executor does not have execute permission");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Load transaction
+    let mut transaction_data = MultisigTransaction::try_from_slice(&transaction_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if transaction_data.multisig != *multisig_account.key {
+        msg!("Transaction does not belong to this multisig");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if transaction_data.executed {
+        msg!("Transaction already executed");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if transaction_data.batch_size == 0 || transaction_data.recipient != *batch_account.key {
+        msg!("Batch does not belong to this transaction");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Check if threshold met
+    if multisig_data.tally(&transaction_data.approvals) < multisig_data.threshold {
+        msg!("Threshold not met");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Load batch
+    let mut batch_data = MultisigBatch::try_from_slice(&batch_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if batch_data.transaction != *transaction_account.key {
+        msg!("Batch does not belong to this transaction");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let vault = Vault::load(
+        program_id,
+        multisig_account,
+        vault_account,
+        vault_token_account,
+        transaction_data.vault_index,
+    )?;
+
+    // Pay the next chunk of transfers in order
+    let start = batch_data.executed_count as usize;
+    let end = batch_data
+        .transfers
+        .len()
+        .min(start + args.max_transfers as usize);
+
+    if start >= end {
+        msg!("No transfers to execute");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    for transfer in &batch_data.transfers[start..end] {
+        let recipient_token_account = next_account_info(accounts_iter)?;
+
+        if *recipient_token_account.key != transfer.recipient_token_account {
+            msg!("Recipient token account does not match batch transfer");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        vault.transfer(recipient_token_account, token_program, transfer.amount)?;
+    }
+
+    batch_data.executed_count = end as u16;
+    batch_data.serialize(&mut &mut batch_account.data.borrow_mut()[..])?;
+
+    if batch_data.is_complete() {
+        transaction_data.executed = true;
+        transaction_data.serialize(&mut &mut transaction_account.data.borrow_mut()[..])?;
+        msg!("Batch executed successfully!");
+    } else {
+        msg!("Batch chunk executed");
+    }
+
+    msg!("Transfers executed: {}/{}", batch_data.executed_count, batch_data.transfers.len());
+
+    Ok(())
+}
+
+// Seeded by [b"vault", multisig, index]
+struct Vault<'a, 'b> {
+    account: &'b AccountInfo<'a>,
+    token_account: &'b AccountInfo<'a>,
+    multisig: Pubkey,
+    index: u8,
+    bump: u8,
+}
+
+impl<'a, 'b> Vault<'a, 'b> {
+    fn load(
+        program_id: &Pubkey,
+        multisig_account: &AccountInfo<'a>,
+        account: &'b AccountInfo<'a>,
+        token_account: &'b AccountInfo<'a>,
+        index: u8,
+    ) -> Result<Self, ProgramError> {
+        let (vault_pda, bump) = Multisig::vault_address(multisig_account.key, index, program_id);
+
+        if vault_pda != *account.key {
+            msg!("Invalid vault PDA");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Ok(Vault {
+            account,
+            token_account,
+            multisig: *multisig_account.key,
+            index,
+            bump,
+        })
+    }
+
+    fn transfer(
+        &self,
+        recipient_token_account: &AccountInfo<'a>,
+        token_program: &AccountInfo<'a>,
+        amount: u64,
+    ) -> ProgramResult {
+        let transfer_instruction = spl_token_2022::instruction::transfer_checked(
+            token_program.key,
+            self.token_account.key,
+            &spl_token_2022::id(),
+            recipient_token_account.key,
+            self.account.key, // Vault PDA authority
+            &[],
+            amount,
+            6,
+        )?;
+
+        invoke_signed(
+            &transfer_instruction,
+            &[
+                self.token_account.clone(),
+                recipient_token_account.clone(),
+                self.account.clone(),
+                token_program.clone(),
+            ],
+            &[&[
+                b"vault",
+                self.multisig.as_ref(),
+                &[self.index],
+                &[self.bump],
+            ]],
+        )
+    }
+}
+
+// Ed25519 signers over `message` in preceding instructions
+fn load_ed25519_signers(
+    instructions_sysvar: &AccountInfo,
+    message: &[u8],
+) -> Result<Vec<Pubkey>, ProgramError> {
+    // Ed25519 instruction layout: [num_signatures: u8, padding: u8, offsets: 14 bytes each]
+    const OFFSETS_START: usize = 2;
+    const OFFSETS_SIZE: usize = 14;
+    const PUBKEY_SIZE: usize = 32;
+    const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+    let read_u16 = |data: &[u8], at: usize| -> Result<u16, ProgramError> {
+        data.get(at..at + 2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+            .ok_or(ProgramError::InvalidInstructionData)
+    };
+
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    let mut signers: Vec<Pubkey> = Vec::new();
+
+    for index in 0..current_index {
+        let instruction = load_instruction_at_checked(index as usize, instructions_sysvar)?;
+        if instruction.program_id != ed25519_program::id() {
+            continue;
+        }
+
+        let data = &instruction.data;
+        let num_signatures = *data.first().ok_or(ProgramError::InvalidInstructionData)? as usize;
+
+        for i in 0..num_signatures {
+            let offsets = OFFSETS_START + i * OFFSETS_SIZE;
+            let signature_instruction_index = read_u16(data, offsets + 2)?;
+            let public_key_offset = read_u16(data, offsets + 4)? as usize;
+            let public_key_instruction_index = read_u16(data, offsets + 6)?;
+            let message_offset = read_u16(data, offsets + 8)? as usize;
+            let message_size = read_u16(data, offsets + 10)? as usize;
+            let message_instruction_index = read_u16(data, offsets + 12)?;
+
+            // Only accept signatures whose data lives inside the Ed25519 instruction itself
+            if signature_instruction_index != CURRENT_INSTRUCTION
+                || public_key_instruction_index != CURRENT_INSTRUCTION
+                || message_instruction_index != CURRENT_INSTRUCTION
+            {
+                msg!("Ed25519 signature references another instruction");
+                return Err(ProgramError::InvalidInstructionData);
+            }
+
+            let signed_message = data
+                .get(message_offset..message_offset + message_size)
+                .ok_or(ProgramError::InvalidInstructionData)?;
+            if signed_message != message {
+                continue;
+            }
+
+            let public_key = data
+                .get(public_key_offset..public_key_offset + PUBKEY_SIZE)
+                .ok_or(ProgramError::InvalidInstructionData)?;
+            let signer = Pubkey::try_from(public_key)
+                .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+            if !signers.contains(&signer) {
+                signers.push(signer);
+            }
+        }
+    }
+
+    Ok(signers)
+}
+
 pub fn process_reject_transaction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -384,26 +1108,56 @@ pub fn process_reject_transaction(
         return Err(ProgramError::MissingRequiredSignature);
     }
 
+    if multisig_account.owner != program_id || transaction_account.owner != program_id {
+        msg!("Multisig accounts must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
     // Load multisig
     let multisig_data = Multisig::try_from_slice(&multisig_account.data.borrow())
         .map_err(|_| ProgramError::InvalidAccountData)?;
 
-    // Verify rejecter is an owner
-    if !multisig_data.owners.contains(rejecter.key) {
-        msg!("Rejecter is not an owner");
+    // Verify rejecter is allowed to vote
+    if !multisig_data.has_permission(rejecter.key, Permissions::VOTE) {
+        msg!("Rejecter does not have vote permission");
         return Err(ProgramError::InvalidAccountData);
     }
 
     // Load transaction
-    let transaction_data = MultisigTransaction::try_from_slice(&transaction_account.data.borrow())
+    let mut transaction_data = MultisigTransaction::try_from_slice(&transaction_account.data.borrow())
         .map_err(|_| ProgramError::InvalidAccountData)?;
 
+    if transaction_data.multisig != *multisig_account.key {
+        msg!("Transaction does not belong to this multisig");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
     if transaction_data.executed {
         msg!("Cannot reject executed transaction");
         return Err(ProgramError::InvalidAccountData);
     }
 
-    msg!("Transaction rejected - account can be closed");
+    // Check if already voted
+    if transaction_data.approvals.contains(rejecter.key)
+        || transaction_data.rejections.contains(rejecter.key)
+    {
+        msg!("Already voted by this owner");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Add rejection
+    transaction_data.rejections.push(*rejecter.key);
+    transaction_data.serialize(&mut &mut transaction_account.data.borrow_mut()[..])?;
+
+    msg!(
+        "Rejection weight: {}/{}",
+        multisig_data.tally(&transaction_data.rejections),
+        multisig_data.total_voting_weight()
+    );
+
+    if multisig_data.is_rejected(&transaction_data) {
+        msg!("Transaction rejected - account can be closed");
+    }
 
     Ok(())
 }
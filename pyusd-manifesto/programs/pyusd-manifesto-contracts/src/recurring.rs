@@ -6,57 +6,448 @@ use solana_program::{
     msg,
     program::{invoke, invoke_signed},
     program_error::ProgramError,
+    program_option::COption,
     pubkey::Pubkey,
     rent::Rent,
     system_instruction,
     sysvar::Sysvar,
 };
+use spl_token_2022::{extension::StateWithExtensions, state::Account as TokenAccount};
+use std::convert::TryFrom;
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
 pub enum PaymentInterval {
     Daily,
     Weekly,
-    Monthly,
+    Monthly, // Calendar month
+    BiWeekly,
+    Quarterly,
+    Yearly,
+    Custom { seconds: i64 },
 }
 
 impl PaymentInterval {
-    pub fn to_seconds(&self) -> i64 {
+    // None for calendar intervals
+    pub fn fixed_seconds(&self) -> Option<i64> {
         match self {
-            PaymentInterval::Daily => 24 * 60 * 60,
-            PaymentInterval::Weekly => 7 * 24 * 60 * 60,
-            PaymentInterval::Monthly => 30 * 24 * 60 * 60,
+            PaymentInterval::Daily => Some(SECONDS_PER_DAY),
+            PaymentInterval::Weekly => Some(7 * SECONDS_PER_DAY),
+            PaymentInterval::BiWeekly => Some(14 * SECONDS_PER_DAY),
+            PaymentInterval::Custom { seconds } => Some(*seconds),
+            _ => None,
         }
     }
+
+    // None for fixed-duration intervals
+    pub fn calendar_months(&self) -> Option<i64> {
+        match self {
+            PaymentInterval::Monthly => Some(1),
+            PaymentInterval::Quarterly => Some(3),
+            PaymentInterval::Yearly => Some(12),
+            _ => None,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        match self {
+            PaymentInterval::Custom { seconds } => *seconds > 0,
+            _ => true,
+        }
+    }
+
+    // Calendar intervals keep the anchor's day, clamped to shorter months
+    pub fn due_time(&self, start_time: i64, index: u64) -> Option<i64> {
+        let index = i64::try_from(index).ok()?;
+        if let Some(seconds) = self.fixed_seconds() {
+            return start_time.checked_add(seconds.checked_mul(index)?);
+        }
+        add_months(start_time, self.calendar_months()?.checked_mul(index)?)
+    }
+
+    // Counts the installment at start_time
+    pub fn periods_elapsed(&self, start_time: i64, current_time: i64) -> u64 {
+        if current_time < start_time {
+            return 0;
+        }
+
+        let estimate = match (self.fixed_seconds(), self.calendar_months()) {
+            (Some(seconds), _) => (current_time - start_time) / seconds,
+            (None, Some(months)) => {
+                let (start_year, start_month, _) =
+                    civil_from_days(start_time.div_euclid(SECONDS_PER_DAY));
+                let (year, month, _) = civil_from_days(current_time.div_euclid(SECONDS_PER_DAY));
+                ((year - start_year) * 12 + (month as i64 - start_month as i64)) / months
+            }
+            (None, None) => return 0,
+        };
+
+        // The calendar estimate can be one period ahead within the anchor month
+        match self.due_time(start_time, estimate as u64) {
+            Some(due) if due <= current_time => estimate as u64 + 1,
+            _ => estimate as u64,
+        }
+    }
+}
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+// Keeps the time of day
+fn add_months(timestamp: i64, months: i64) -> Option<i64> {
+    let days = timestamp.div_euclid(SECONDS_PER_DAY);
+    let time_of_day = timestamp.rem_euclid(SECONDS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+
+    let total_months = (year * 12 + (month as i64 - 1)).checked_add(months)?;
+    let new_year = total_months.div_euclid(12);
+    let new_month = (total_months.rem_euclid(12) + 1) as u32;
+    let new_day = day.min(days_in_month(new_year, new_month));
+
+    days_from_civil(new_year, new_month, new_day)
+        .checked_mul(SECONDS_PER_DAY)?
+        .checked_add(time_of_day)
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Proleptic Gregorian calendar conversions (Howard Hinnant's algorithms)
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum PaymentStatus {
+    Active,
+    Paused,
+    Completed,
+    Cancelled,
+    PendingAcceptance, // Waiting for the recipient to accept
+    Terminated,        // Ended or declined by the recipient
+    Lapsed,            // Payer could not cover an installment within the grace period
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct RecurringPayment {
     pub creator: Pubkey,
     pub recipient: Pubkey,
+    pub source_token_account: Pubkey, // Delegated creator account, or the PDA-owned vault when prefunded
+    pub recipient_token_account: Pubkey,
     pub amount_per_payment: u64,
     pub interval: PaymentInterval,
+    pub prefunded: bool, // Whole schedule locked in the vault at creation
+    pub start_time: i64, // Schedule anchor, due date of the first installment
     pub total_payments: u64,
     pub payments_made: u64,
+    pub next_period: u64,          // Installments settled so far, paid or skipped
+    pub max_catch_up_periods: u16, // Missed periods paid in arrears, 0 for no cap
     pub last_payment_time: i64,
-    pub is_active: bool,
+    pub status: PaymentStatus,
+    pub paused_at: i64,      // Start of the current pause, 0 when not paused
+    pub paused_seconds: i64, // Total time spent paused, excluded from the schedule
+    pub keeper_tip_lamports: u64, // Paid to the executor from the lamport reserve on this account
+    pub last_tip_time: i64,
+    pub grace_period_seconds: i64, // Retry window after the first failed execution
+    pub failed_attempts: u16,
+    pub first_failure_time: i64, // 0 when the last execution succeeded
     pub created_at: i64,
     pub description: String,
     pub bump: u8,
 }
 
 impl RecurringPayment {
-    pub const MAX_SIZE: usize = 32 + 32 + 8 + 1 + 8 + 8 + 8 + 1 + 8 + 4 + 100 + 1; // ~210 bytes
+    pub const MAX_SIZE: usize = 32 + 32 + 32 + 32 // creator, recipient, token accounts
+        + 8 + 9 + 1 + 8 // amount, interval, prefunded, start_time
+        + 8 + 8 + 8 + 2 + 8 // payment progress
+        + 1 + 8 + 8 // status, pause tracking
+        + 8 + 2 + 8 // failure tracking
+        + 8 + 8 + 8 + 4 + 100 + 1; // ~354 bytes
+
+    pub const MAX_PERIODS_PER_EXECUTION: u64 = 12;
+
+    // Pushed back by time spent paused
+    pub fn due_time(&self, index: u64) -> Option<i64> {
+        self.interval
+            .due_time(self.start_time, index)?
+            .checked_add(self.paused_seconds)
+    }
+
+    // Excludes paused time
+    fn schedule_time(&self, current_time: i64) -> i64 {
+        let now = if self.paused_at > 0 {
+            self.paused_at
+        } else {
+            current_time
+        };
+        now.saturating_sub(self.paused_seconds)
+    }
+
+    pub fn outstanding_periods(&self, current_time: i64) -> u64 {
+        self.interval
+            .periods_elapsed(self.start_time, self.schedule_time(current_time))
+            .min(self.total_payments)
+            .saturating_sub(self.next_period)
+    }
+
+    // Stale schedules forfeit periods beyond the cap so they don't drain the payer
+    pub fn apply_catch_up_cap(&mut self, current_time: i64) -> u64 {
+        let outstanding = self.outstanding_periods(current_time);
+        let cap = self.max_catch_up_periods as u64;
+
+        if cap > 0 && outstanding > cap {
+            let skipped = outstanding - cap;
+            self.next_period += skipped;
+            msg!("Skipped {} missed payments beyond catch-up cap", skipped);
+            return cap;
+        }
+
+        outstanding
+    }
+
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self.status,
+            PaymentStatus::Active | PaymentStatus::Paused | PaymentStatus::PendingAcceptance
+        )
+    }
+
+    pub fn remaining_periods(&self) -> u64 {
+        self.total_payments.saturating_sub(self.next_period)
+    }
+
+    // Late executions catch up in quick succession, so one tip per interval
+    pub fn is_tip_due(&self, current_time: i64) -> bool {
+        let elapsed = self
+            .interval
+            .periods_elapsed(self.start_time, self.schedule_time(current_time));
+        match elapsed.checked_sub(1).and_then(|index| self.due_time(index)) {
+            Some(period_start) => self.last_tip_time < period_start,
+            None => false,
+        }
+    }
+}
+
+// Payment PDA signs as delegate of the creator account or owner of the vault
+fn transfer_from_source<'a>(
+    payment_data: &RecurringPayment,
+    recurring_payment_account: &AccountInfo<'a>,
+    source_token_account: &AccountInfo<'a>,
+    destination_token_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    let transfer_instruction = spl_token_2022::instruction::transfer_checked(
+        token_program.key,
+        source_token_account.key,
+        &spl_token_2022::id(),
+        destination_token_account.key,
+        recurring_payment_account.key, // PDA authority
+        &[],
+        amount,
+        6, // PYUSD decimals
+    )?;
+
+    invoke_signed(
+        &transfer_instruction,
+        &[
+            source_token_account.clone(),
+            destination_token_account.clone(),
+            recurring_payment_account.clone(),
+            token_program.clone(),
+        ],
+        &[&[
+            b"recurring_payment",
+            payment_data.creator.as_ref(),
+            &payment_data.created_at.to_le_bytes(),
+            &[payment_data.bump],
+        ]],
+    )
+}
+
+fn available_to_pay(
+    payment_data: &RecurringPayment,
+    recurring_payment_account: &AccountInfo,
+    source_token_account: &AccountInfo,
+) -> Result<u64, ProgramError> {
+    let source = unpack_token_account(source_token_account)?;
+
+    if source.is_frozen() {
+        return Ok(0);
+    }
+
+    if payment_data.prefunded {
+        return Ok(source.amount);
+    }
+
+    // Delegated payments are limited by the remaining allowance
+    if source.delegate != COption::Some(*recurring_payment_account.key) {
+        return Ok(0);
+    }
+
+    Ok(source.amount.min(source.delegated_amount))
+}
+
+pub(crate) fn unpack_token_account(account: &AccountInfo) -> Result<TokenAccount, ProgramError> {
+    let data = account.data.borrow();
+    let state = StateWithExtensions::<TokenAccount>::unpack(&data)?;
+    Ok(state.base)
+}
+
+// A token account holds one delegate, so don't take another payment's allowance
+pub(crate) fn ensure_delegate_available(token_account: &AccountInfo, delegate: &Pubkey) -> ProgramResult {
+    let account = unpack_token_account(token_account)?;
+
+    if let COption::Some(current) = account.delegate {
+        if current != *delegate && account.delegated_amount > 0 {
+            msg!("Token account already delegates to {}, use a separate token account", current);
+            return Err(ProgramError::InvalidAccountData);
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn is_current_delegate(token_account: &AccountInfo, delegate: &Pubkey) -> Result<bool, ProgramError> {
+    Ok(unpack_token_account(token_account)?.delegate == COption::Some(*delegate))
+}
+
+fn keeper_reserve(recurring_payment_account: &AccountInfo, rent: &Rent) -> u64 {
+    recurring_payment_account
+        .lamports()
+        .saturating_sub(rent.minimum_balance(recurring_payment_account.data_len()))
+}
+
+fn refund_keeper_reserve(recurring_payment_account: &AccountInfo, creator: &AccountInfo) -> ProgramResult {
+    let rent = Rent::get()?;
+    let refund = keeper_reserve(recurring_payment_account, &rent);
+    if refund > 0 {
+        **recurring_payment_account.try_borrow_mut_lamports()? -= refund;
+        **creator.try_borrow_mut_lamports()? += refund;
+        msg!("Keeper reserve refunded: {} lamports", refund);
+    }
+    Ok(())
+}
+
+// Due installments go to the recipient, the rest back to the creator
+fn settle_prefunded_vault<'a>(
+    payment_data: &mut RecurringPayment,
+    recurring_payment_account: &AccountInfo<'a>,
+    vault_token_account: &AccountInfo<'a>,
+    recipient_token_account: &AccountInfo<'a>,
+    creator_token_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    if payment_data.source_token_account != *vault_token_account.key {
+        msg!("Invalid vault token account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if payment_data.recipient_token_account != *recipient_token_account.key {
+        msg!("Invalid recipient token account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Installments already due belong to the recipient, once they have accepted
+    if payment_data.status != PaymentStatus::PendingAcceptance {
+        let clock = Clock::get()?;
+        let due_periods = payment_data.apply_catch_up_cap(clock.unix_timestamp);
+        let due_amount = payment_data
+            .amount_per_payment
+            .checked_mul(due_periods)
+            .ok_or(ProgramError::InvalidAccountData)?;
+
+        if due_amount > 0 {
+            transfer_from_source(
+                payment_data,
+                recurring_payment_account,
+                vault_token_account,
+                recipient_token_account,
+                token_program,
+                due_amount,
+            )?;
+
+            payment_data.payments_made += due_periods;
+            payment_data.next_period += due_periods;
+            payment_data.last_payment_time = clock.unix_timestamp;
+            msg!("Paid due amount to recipient: {}", due_amount);
+        }
+    }
+
+    // Everything not yet due goes back to the creator, including skipped periods
+    let refund_amount = unpack_token_account(vault_token_account)?.amount;
+
+    if refund_amount > 0 {
+        transfer_from_source(
+            payment_data,
+            recurring_payment_account,
+            vault_token_account,
+            creator_token_account,
+            token_program,
+            refund_amount,
+        )?;
+        msg!("Refunded unpaid amount to creator: {}", refund_amount);
+    }
+
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct AmendRecurringPaymentArgs {
+    pub amount_per_payment: u64,
+    pub recipient: Pubkey,
+    pub recipient_token_account: Pubkey,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct CreateRecurringPaymentArgs {
     pub recipient: Pubkey,
+    pub recipient_token_account: Pubkey,
     pub amount_per_payment: u64,
     pub interval: PaymentInterval,
+    pub prefunded: bool,
+    pub start_time: i64, // 0 starts the schedule immediately
     pub total_payments: u64,
+    pub max_catch_up_periods: u16,
+    pub keeper_tip_lamports: u64,
+    pub require_acceptance: bool, // Hold payments until the recipient accepts
+    pub grace_period_seconds: i64,
     pub description: String,
 }
 
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct RedirectRecurringPaymentArgs {
+    pub new_recipient: Pubkey,
+}
+
 pub fn process_create_recurring_payment(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -66,6 +457,8 @@ pub fn process_create_recurring_payment(
 
     let recurring_payment_account = next_account_info(accounts_iter)?;
     let creator = next_account_info(accounts_iter)?;
+    let creator_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
     let system_program = next_account_info(accounts_iter)?;
 
     if !creator.is_signer {
@@ -87,9 +480,46 @@ pub fn process_create_recurring_payment(
         return Err(ProgramError::InvalidArgument);
     }
 
+    if !args.interval.is_valid() {
+        msg!("Invalid payment interval");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if args.grace_period_seconds < 0 {
+        msg!("Grace period cannot be negative");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let schedule_amount = args
+        .amount_per_payment
+        .checked_mul(args.total_payments)
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    let keeper_reserve_lamports = args
+        .keeper_tip_lamports
+        .checked_mul(args.total_payments)
+        .ok_or(ProgramError::InvalidArgument)?;
+
     let clock = Clock::get()?;
     let current_time = clock.unix_timestamp;
 
+    let start_time = if args.start_time == 0 {
+        current_time
+    } else {
+        args.start_time
+    };
+
+    if start_time < current_time {
+        msg!("Start time cannot be in the past");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // The final installment must have a representable due date
+    if args.interval.due_time(start_time, args.total_payments - 1).is_none() {
+        msg!("Payment schedule is too long");
+        return Err(ProgramError::InvalidArgument);
+    }
+
     // Derive PDA
     let (payment_pda, bump) = Pubkey::find_program_address(
         &[
@@ -105,10 +535,13 @@ pub fn process_create_recurring_payment(
         return Err(ProgramError::InvalidArgument);
     }
 
-    // Create recurring payment account
+    // Create recurring payment account, funding the keeper tip reserve up front
     let rent = Rent::get()?;
     let space = RecurringPayment::MAX_SIZE;
-    let lamports = rent.minimum_balance(space);
+    let lamports = rent
+        .minimum_balance(space)
+        .checked_add(keeper_reserve_lamports)
+        .ok_or(ProgramError::InvalidArgument)?;
 
     invoke_signed(
         &system_instruction::create_account(
@@ -127,16 +560,48 @@ pub fn process_create_recurring_payment(
         ]],
     )?;
 
+    // Prefunded payments pay out of a vault token account owned by the payment PDA
+    let source_token_account = if args.prefunded {
+        let vault_token_account = next_account_info(accounts_iter)?;
+        let vault = unpack_token_account(vault_token_account)?;
+
+        if vault.owner != *recurring_payment_account.key {
+            msg!("Vault token account must be owned by the payment PDA");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        vault_token_account
+    } else {
+        creator_token_account
+    };
+
     // Initialize recurring payment data
     let payment_data = RecurringPayment {
         creator: *creator.key,
         recipient: args.recipient,
+        source_token_account: *source_token_account.key,
+        recipient_token_account: args.recipient_token_account,
         amount_per_payment: args.amount_per_payment,
         interval: args.interval,
+        prefunded: args.prefunded,
+        start_time,
         total_payments: args.total_payments,
         payments_made: 0,
+        next_period: 0,
+        max_catch_up_periods: args.max_catch_up_periods,
         last_payment_time: 0, // No payments made yet
-        is_active: true,
+        status: if args.require_acceptance {
+            PaymentStatus::PendingAcceptance
+        } else {
+            PaymentStatus::Active
+        },
+        paused_at: 0,
+        paused_seconds: 0,
+        keeper_tip_lamports: args.keeper_tip_lamports,
+        last_tip_time: 0,
+        grace_period_seconds: args.grace_period_seconds,
+        failed_attempts: 0,
+        first_failure_time: 0,
         created_at: current_time,
         description: args.description,
         bump,
@@ -144,15 +609,70 @@ pub fn process_create_recurring_payment(
 
     payment_data.serialize(&mut &mut recurring_payment_account.data.borrow_mut()[..])?;
 
+    if args.prefunded {
+        // Lock the full schedule in the vault
+        let transfer_instruction = spl_token_2022::instruction::transfer_checked(
+            token_program.key,
+            creator_token_account.key,
+            &spl_token_2022::id(),
+            source_token_account.key,
+            creator.key,
+            &[],
+            schedule_amount,
+            6, // PYUSD decimals
+        )?;
+
+        invoke(
+            &transfer_instruction,
+            &[
+                creator_token_account.clone(),
+                source_token_account.clone(),
+                creator.clone(),
+                token_program.clone(),
+            ],
+        )?;
+    } else {
+        ensure_delegate_available(creator_token_account, recurring_payment_account.key)?;
+
+        // Delegate the full schedule to the payment PDA so anyone can crank execution
+        let approve_instruction = spl_token_2022::instruction::approve(
+            token_program.key,
+            creator_token_account.key,
+            recurring_payment_account.key,
+            creator.key,
+            &[],
+            schedule_amount,
+        )?;
+
+        invoke(
+            &approve_instruction,
+            &[
+                creator_token_account.clone(),
+                recurring_payment_account.clone(),
+                creator.clone(),
+                token_program.clone(),
+            ],
+        )?;
+    }
+
     msg!("Recurring payment created successfully!");
     msg!("Payment PDA: {}", recurring_payment_account.key);
     msg!("Recipient: {}", args.recipient);
     msg!("Amount per payment: {}", args.amount_per_payment);
     msg!("Total payments: {}", args.total_payments);
+    msg!("First payment due: {}", start_time);
+    if args.prefunded {
+        msg!("Prefunded amount: {}", schedule_amount);
+    } else {
+        msg!("Delegated amount: {}", schedule_amount);
+    }
+    msg!("Keeper reserve: {} lamports", keeper_reserve_lamports);
 
     Ok(())
 }
 
+// Callable by anyone. The completing execution must also pass the creator,
+// and for prefunded payments the creator's token account, to refund leftovers
 pub fn process_execute_recurring_payment(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -161,31 +681,41 @@ pub fn process_execute_recurring_payment(
     let accounts_iter = &mut accounts.iter();
 
     let recurring_payment_account = next_account_info(accounts_iter)?;
-    let creator_token_account = next_account_info(accounts_iter)?;
+    let source_token_account = next_account_info(accounts_iter)?;
     let recipient_token_account = next_account_info(accounts_iter)?;
-    let creator = next_account_info(accounts_iter)?;
+    let executor = next_account_info(accounts_iter)?;
     let token_program = next_account_info(accounts_iter)?;
 
-    if !creator.is_signer {
-        msg!("Creator must be a signer");
+    if !executor.is_signer {
+        msg!("Executor must be a signer");
         return Err(ProgramError::MissingRequiredSignature);
     }
 
+    if recurring_payment_account.owner != program_id {
+        msg!("Recurring payment must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
     // Load payment data
     let mut payment_data = RecurringPayment::try_from_slice(&recurring_payment_account.data.borrow())
         .map_err(|_| ProgramError::InvalidAccountData)?;
 
-    if payment_data.creator != *creator.key {
-        msg!("Only creator can execute payment");
+    if payment_data.source_token_account != *source_token_account.key {
+        msg!("Invalid source token account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if payment_data.recipient_token_account != *recipient_token_account.key {
+        msg!("Invalid recipient token account");
         return Err(ProgramError::InvalidAccountData);
     }
 
-    if !payment_data.is_active {
+    if payment_data.status != PaymentStatus::Active {
         msg!("Recurring payment is not active");
         return Err(ProgramError::InvalidAccountData);
     }
 
-    if payment_data.payments_made >= payment_data.total_payments {
+    if payment_data.next_period >= payment_data.total_payments {
         msg!("All payments have been made");
         return Err(ProgramError::InvalidAccountData);
     }
@@ -193,60 +723,147 @@ pub fn process_execute_recurring_payment(
     let clock = Clock::get()?;
     let current_time = clock.unix_timestamp;
 
-    // Check if enough time has passed since last payment
-    if payment_data.last_payment_time > 0 {
-        let time_since_last = current_time - payment_data.last_payment_time;
-        let interval_seconds = payment_data.interval.to_seconds();
+    // Count installments due according to the schedule
+    if payment_data.outstanding_periods(current_time) == 0 {
+        msg!("Payment not due yet");
+        if let Some(due_time) = payment_data.due_time(payment_data.next_period) {
+            msg!("Next payment due: {}", due_time);
+        }
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let outstanding = payment_data.apply_catch_up_cap(current_time);
+    let due_periods = outstanding.min(RecurringPayment::MAX_PERIODS_PER_EXECUTION);
 
-        if time_since_last < interval_seconds {
-            msg!("Payment interval not reached yet");
-            msg!("Time since last payment: {} seconds", time_since_last);
-            msg!("Required interval: {} seconds", interval_seconds);
-            return Err(ProgramError::InvalidAccountData);
+    // Pay as many whole periods as the payer can cover; the rest stays outstanding
+    let available = available_to_pay(&payment_data, recurring_payment_account, source_token_account)?;
+    let periods = due_periods.min(available / payment_data.amount_per_payment);
+
+    // Record a failed attempt instead of failing the transfer, so retries and
+    // the lapse deadline are tracked on chain
+    if periods == 0 {
+        if payment_data.first_failure_time == 0 {
+            payment_data.first_failure_time = current_time;
         }
+        payment_data.failed_attempts = payment_data.failed_attempts.saturating_add(1);
+
+        msg!(
+            "Insufficient balance or allowance: {} available, {} due",
+            available,
+            payment_data.amount_per_payment
+        );
+        msg!("Failed attempts: {}", payment_data.failed_attempts);
+
+        let failing_for = current_time.saturating_sub(payment_data.first_failure_time);
+        if failing_for >= payment_data.grace_period_seconds {
+            payment_data.status = PaymentStatus::Lapsed;
+            msg!("Grace period expired, recurring payment lapsed");
+        } else {
+            msg!(
+                "Retry allowed until: {}",
+                payment_data
+                    .first_failure_time
+                    .saturating_add(payment_data.grace_period_seconds)
+            );
+        }
+
+        payment_data.serialize(&mut &mut recurring_payment_account.data.borrow_mut()[..])?;
+        return Ok(());
     }
 
-    // Execute payment transfer
-    let transfer_instruction = spl_token_2022::instruction::transfer_checked(
-        token_program.key,
-        creator_token_account.key,
-        &spl_token_2022::id(),
-        recipient_token_account.key,
-        creator.key,
-        &[],
-        payment_data.amount_per_payment,
-        6, // PYUSD decimals
-    )?;
+    let amount = payment_data
+        .amount_per_payment
+        .checked_mul(periods)
+        .ok_or(ProgramError::InvalidAccountData)?;
 
-    invoke(
-        &transfer_instruction,
-        &[
-            creator_token_account.clone(),
-            recipient_token_account.clone(),
-            creator.clone(),
-            token_program.clone(),
-        ],
+    if periods < due_periods {
+        msg!("Paying {} of {} due periods, balance covers no more", periods, due_periods);
+    }
+
+    // Execute payment transfer
+    transfer_from_source(
+        &payment_data,
+        recurring_payment_account,
+        source_token_account,
+        recipient_token_account,
+        token_program,
+        amount,
     )?;
 
     // Update payment data
-    payment_data.payments_made += 1;
+    payment_data.payments_made += periods;
+    payment_data.next_period += periods;
     payment_data.last_payment_time = current_time;
+    payment_data.failed_attempts = 0;
+    payment_data.first_failure_time = 0;
+
+    // Pay the keeper tip from the reserve
+    let tip = payment_data.keeper_tip_lamports;
+    if tip > 0 && payment_data.is_tip_due(current_time) {
+        let rent = Rent::get()?;
+        if keeper_reserve(recurring_payment_account, &rent) >= tip {
+            **recurring_payment_account.try_borrow_mut_lamports()? -= tip;
+            **executor.try_borrow_mut_lamports()? += tip;
+            payment_data.last_tip_time = current_time;
+            msg!("Keeper tip paid: {} lamports", tip);
+        } else {
+            msg!("Keeper reserve exhausted, no tip paid");
+        }
+    }
 
     // Deactivate if all payments completed
-    if payment_data.payments_made >= payment_data.total_payments {
-        payment_data.is_active = false;
+    let completed = payment_data.next_period >= payment_data.total_payments;
+    if completed {
+        payment_data.status = PaymentStatus::Completed;
         msg!("All recurring payments completed!");
     }
 
     payment_data.serialize(&mut &mut recurring_payment_account.data.borrow_mut()[..])?;
 
+    // Catch-up executions pay one tip for several periods, so the reserve
+    // usually outlives the schedule
+    if completed {
+        let creator = next_account_info(accounts_iter)?;
+
+        if payment_data.creator != *creator.key {
+            msg!("Invalid creator account");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        refund_keeper_reserve(recurring_payment_account, creator)?;
+
+        if payment_data.prefunded {
+            let creator_token_account = next_account_info(accounts_iter)?;
+
+            if unpack_token_account(creator_token_account)?.owner != payment_data.creator {
+                msg!("Refund token account must belong to the creator");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let refund_amount = unpack_token_account(source_token_account)?.amount;
+            if refund_amount > 0 {
+                transfer_from_source(
+                    &payment_data,
+                    recurring_payment_account,
+                    source_token_account,
+                    creator_token_account,
+                    token_program,
+                    refund_amount,
+                )?;
+                msg!("Refunded unpaid amount to creator: {}", refund_amount);
+            }
+        }
+    }
+
     msg!("Recurring payment executed successfully!");
     msg!("Payments made: {}/{}", payment_data.payments_made, payment_data.total_payments);
-    msg!("Amount transferred: {}", payment_data.amount_per_payment);
+    msg!("Periods paid: {}", periods);
+    msg!("Amount transferred: {}", amount);
 
     Ok(())
 }
 
+// Prefunded payments pay installments already due and refund the rest
 pub fn process_cancel_recurring_payment(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -256,12 +873,19 @@ pub fn process_cancel_recurring_payment(
 
     let recurring_payment_account = next_account_info(accounts_iter)?;
     let creator = next_account_info(accounts_iter)?;
+    let creator_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
 
     if !creator.is_signer {
         msg!("Creator must be a signer");
         return Err(ProgramError::MissingRequiredSignature);
     }
 
+    if recurring_payment_account.owner != program_id {
+        msg!("Recurring payment must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
     // Load payment data
     let mut payment_data = RecurringPayment::try_from_slice(&recurring_payment_account.data.borrow())
         .map_err(|_| ProgramError::InvalidAccountData)?;
@@ -271,17 +895,531 @@ pub fn process_cancel_recurring_payment(
         return Err(ProgramError::InvalidAccountData);
     }
 
-    if !payment_data.is_active {
+    if !payment_data.is_open() && payment_data.status != PaymentStatus::Lapsed {
         msg!("Recurring payment is already inactive");
         return Err(ProgramError::InvalidAccountData);
     }
 
+    if payment_data.prefunded {
+        let vault_token_account = next_account_info(accounts_iter)?;
+        let recipient_token_account = next_account_info(accounts_iter)?;
+
+        settle_prefunded_vault(
+            &mut payment_data,
+            recurring_payment_account,
+            vault_token_account,
+            recipient_token_account,
+            creator_token_account,
+            token_program,
+        )?;
+    } else {
+        if payment_data.source_token_account != *creator_token_account.key {
+            msg!("Invalid creator token account");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Revoke the remaining delegation, unless another approval replaced it
+        if is_current_delegate(creator_token_account, recurring_payment_account.key)? {
+            let revoke_instruction = spl_token_2022::instruction::revoke(
+                token_program.key,
+                creator_token_account.key,
+                creator.key,
+                &[],
+            )?;
+
+            invoke(
+                &revoke_instruction,
+                &[
+                    creator_token_account.clone(),
+                    creator.clone(),
+                    token_program.clone(),
+                ],
+            )?;
+        } else {
+            msg!("Token account no longer delegates to this payment, nothing to revoke");
+        }
+    }
+
     // Deactivate the recurring payment
-    payment_data.is_active = false;
+    payment_data.status = PaymentStatus::Cancelled;
     payment_data.serialize(&mut &mut recurring_payment_account.data.borrow_mut()[..])?;
 
+    refund_keeper_reserve(recurring_payment_account, creator)?;
+
     msg!("Recurring payment cancelled successfully!");
     msg!("Payments made before cancellation: {}/{}", payment_data.payments_made, payment_data.total_payments);
 
     Ok(())
 }
+
+pub fn process_pause_recurring_payment(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let recurring_payment_account = next_account_info(accounts_iter)?;
+    let creator = next_account_info(accounts_iter)?;
+
+    if !creator.is_signer {
+        msg!("Creator must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if recurring_payment_account.owner != program_id {
+        msg!("Recurring payment must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Load payment data
+    let mut payment_data = RecurringPayment::try_from_slice(&recurring_payment_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if payment_data.creator != *creator.key {
+        msg!("Only creator can pause payment");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if payment_data.status != PaymentStatus::Active {
+        msg!("Recurring payment is not active");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let clock = Clock::get()?;
+
+    payment_data.status = PaymentStatus::Paused;
+    payment_data.paused_at = clock.unix_timestamp;
+    payment_data.serialize(&mut &mut recurring_payment_account.data.borrow_mut()[..])?;
+
+    msg!("Recurring payment paused");
+
+    Ok(())
+}
+
+pub fn process_resume_recurring_payment(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let recurring_payment_account = next_account_info(accounts_iter)?;
+    let creator = next_account_info(accounts_iter)?;
+
+    if !creator.is_signer {
+        msg!("Creator must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if recurring_payment_account.owner != program_id {
+        msg!("Recurring payment must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Load payment data
+    let mut payment_data = RecurringPayment::try_from_slice(&recurring_payment_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if payment_data.creator != *creator.key {
+        msg!("Only creator can resume payment");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if payment_data.status != PaymentStatus::Paused {
+        msg!("Recurring payment is not paused");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let clock = Clock::get()?;
+    let paused_for = clock.unix_timestamp.saturating_sub(payment_data.paused_at);
+
+    // Shift the remaining schedule by the time spent paused
+    payment_data.paused_seconds = payment_data.paused_seconds.saturating_add(paused_for);
+    payment_data.paused_at = 0;
+    payment_data.status = PaymentStatus::Active;
+    payment_data.serialize(&mut &mut recurring_payment_account.data.borrow_mut()[..])?;
+
+    msg!("Recurring payment resumed after {} seconds", paused_for);
+    if let Some(due_time) = payment_data.due_time(payment_data.next_period) {
+        msg!("Next payment due: {}", due_time);
+    }
+
+    Ok(())
+}
+
+// Lowering the amount or changing the recipient needs the recipient's co-sign
+pub fn process_amend_recurring_payment(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let recurring_payment_account = next_account_info(accounts_iter)?;
+    let creator = next_account_info(accounts_iter)?;
+    let creator_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if !creator.is_signer {
+        msg!("Creator must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if recurring_payment_account.owner != program_id {
+        msg!("Recurring payment must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let args = AmendRecurringPaymentArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    if args.amount_per_payment == 0 {
+        msg!("Amount per payment must be greater than 0");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Load payment data
+    let mut payment_data = RecurringPayment::try_from_slice(&recurring_payment_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if payment_data.creator != *creator.key {
+        msg!("Only creator can amend payment");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if !payment_data.is_open() {
+        msg!("Recurring payment is not active");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if payment_data.prefunded && args.amount_per_payment != payment_data.amount_per_payment {
+        msg!("Cannot change the amount of a prefunded payment");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if payment_data.source_token_account != *creator_token_account.key && !payment_data.prefunded {
+        msg!("Invalid creator token account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // The recipient must agree to being paid less, or not at all
+    if args.amount_per_payment < payment_data.amount_per_payment
+        || args.recipient != payment_data.recipient
+    {
+        let recipient = next_account_info(accounts_iter)?;
+
+        if *recipient.key != payment_data.recipient || !recipient.is_signer {
+            msg!("Recipient must co-sign a lower amount or a new recipient");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+    }
+
+    if args.recipient != payment_data.recipient
+        || args.recipient_token_account != payment_data.recipient_token_account
+    {
+        let recipient_token_account = next_account_info(accounts_iter)?;
+
+        if *recipient_token_account.key != args.recipient_token_account
+            || unpack_token_account(recipient_token_account)?.owner != args.recipient
+        {
+            msg!("Recipient token account must belong to the recipient");
+            return Err(ProgramError::InvalidAccountData);
+        }
+    }
+
+    let delegated_amount = args
+        .amount_per_payment
+        .checked_mul(payment_data.remaining_periods())
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    payment_data.amount_per_payment = args.amount_per_payment;
+    payment_data.recipient = args.recipient;
+    payment_data.recipient_token_account = args.recipient_token_account;
+    payment_data.serialize(&mut &mut recurring_payment_account.data.borrow_mut()[..])?;
+
+    // Re-delegate the remaining schedule at the new amount
+    if !payment_data.prefunded {
+        ensure_delegate_available(creator_token_account, recurring_payment_account.key)?;
+
+        let approve_instruction = spl_token_2022::instruction::approve(
+            token_program.key,
+            creator_token_account.key,
+            recurring_payment_account.key,
+            creator.key,
+            &[],
+            delegated_amount,
+        )?;
+
+        invoke(
+            &approve_instruction,
+            &[
+                creator_token_account.clone(),
+                recurring_payment_account.clone(),
+                creator.clone(),
+                token_program.clone(),
+            ],
+        )?;
+    }
+
+    msg!("Recurring payment amended successfully!");
+    msg!("Recipient: {}", args.recipient);
+    msg!("Amount per payment: {}", args.amount_per_payment);
+    msg!("Delegated amount: {}", delegated_amount);
+
+    Ok(())
+}
+
+pub fn process_accept_recurring_payment(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let recurring_payment_account = next_account_info(accounts_iter)?;
+    let recipient = next_account_info(accounts_iter)?;
+
+    if !recipient.is_signer {
+        msg!("Recipient must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if recurring_payment_account.owner != program_id {
+        msg!("Recurring payment must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Load payment data
+    let mut payment_data = RecurringPayment::try_from_slice(&recurring_payment_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if payment_data.recipient != *recipient.key {
+        msg!("Only recipient can accept payment");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if payment_data.status != PaymentStatus::PendingAcceptance {
+        msg!("Recurring payment is not awaiting acceptance");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    payment_data.status = PaymentStatus::Active;
+    payment_data.serialize(&mut &mut recurring_payment_account.data.borrow_mut()[..])?;
+
+    msg!("Recurring payment accepted by recipient");
+
+    Ok(())
+}
+
+// A delegated allowance can only be revoked by the creator but is no longer usable
+pub fn process_terminate_recurring_payment(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let recurring_payment_account = next_account_info(accounts_iter)?;
+    let recipient = next_account_info(accounts_iter)?;
+    let creator = next_account_info(accounts_iter)?;
+
+    if !recipient.is_signer {
+        msg!("Recipient must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if recurring_payment_account.owner != program_id {
+        msg!("Recurring payment must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Load payment data
+    let mut payment_data = RecurringPayment::try_from_slice(&recurring_payment_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if payment_data.recipient != *recipient.key {
+        msg!("Only recipient can terminate payment");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if payment_data.creator != *creator.key {
+        msg!("Invalid creator account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if !payment_data.is_open() {
+        msg!("Recurring payment is already inactive");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if payment_data.prefunded {
+        let creator_token_account = next_account_info(accounts_iter)?;
+        let token_program = next_account_info(accounts_iter)?;
+        let vault_token_account = next_account_info(accounts_iter)?;
+        let recipient_token_account = next_account_info(accounts_iter)?;
+
+        if unpack_token_account(creator_token_account)?.owner != payment_data.creator {
+            msg!("Refund token account must belong to the creator");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        settle_prefunded_vault(
+            &mut payment_data,
+            recurring_payment_account,
+            vault_token_account,
+            recipient_token_account,
+            creator_token_account,
+            token_program,
+        )?;
+    }
+
+    payment_data.status = PaymentStatus::Terminated;
+    payment_data.serialize(&mut &mut recurring_payment_account.data.borrow_mut()[..])?;
+
+    refund_keeper_reserve(recurring_payment_account, creator)?;
+
+    msg!("Recurring payment terminated by recipient");
+    msg!("Payments made before termination: {}/{}", payment_data.payments_made, payment_data.total_payments);
+
+    Ok(())
+}
+
+pub fn process_redirect_recurring_payment(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let recurring_payment_account = next_account_info(accounts_iter)?;
+    let recipient = next_account_info(accounts_iter)?;
+    let new_recipient_token_account = next_account_info(accounts_iter)?;
+
+    if !recipient.is_signer {
+        msg!("Recipient must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if recurring_payment_account.owner != program_id {
+        msg!("Recurring payment must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let args = RedirectRecurringPaymentArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    // Load payment data
+    let mut payment_data = RecurringPayment::try_from_slice(&recurring_payment_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if payment_data.recipient != *recipient.key {
+        msg!("Only recipient can redirect payment");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if !payment_data.is_open() {
+        msg!("Recurring payment is not active");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if unpack_token_account(new_recipient_token_account)?.owner != args.new_recipient {
+        msg!("New token account must belong to the new recipient");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    payment_data.recipient = args.new_recipient;
+    payment_data.recipient_token_account = *new_recipient_token_account.key;
+    payment_data.serialize(&mut &mut recurring_payment_account.data.borrow_mut()[..])?;
+
+    msg!("Recurring payment redirected");
+    msg!("New recipient: {}", args.new_recipient);
+    msg!("New recipient token account: {}", new_recipient_token_account.key);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JAN_31_2024: i64 = 1_706_659_200;
+    const FEB_15_2024: i64 = 1_707_955_200;
+    const FEB_29_2024: i64 = 1_709_164_800;
+    const MAR_31_2024: i64 = 1_711_843_200;
+    const APR_30_2024: i64 = 1_714_435_200;
+    const FEB_28_2025: i64 = 1_740_700_800;
+
+    #[test]
+    fn civil_conversions_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(JAN_31_2024 / SECONDS_PER_DAY), (2024, 1, 31));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+
+        for days in -800_000..800_000 {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn leap_years() {
+        assert!(is_leap_year(2024));
+        assert!(is_leap_year(2000));
+        assert!(!is_leap_year(1900));
+        assert!(!is_leap_year(2023));
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2100, 2), 28);
+    }
+
+    #[test]
+    fn add_months_clamps_to_month_end() {
+        assert_eq!(add_months(JAN_31_2024, 1), Some(FEB_29_2024));
+        assert_eq!(add_months(JAN_31_2024, 2), Some(MAR_31_2024));
+        assert_eq!(add_months(JAN_31_2024, 3), Some(APR_30_2024));
+        assert_eq!(add_months(FEB_29_2024, 12), Some(FEB_28_2025));
+        assert_eq!(add_months(JAN_31_2024 + 3600, 1), Some(FEB_29_2024 + 3600));
+        assert_eq!(add_months(FEB_29_2024, -1), Some(JAN_31_2024 - 2 * SECONDS_PER_DAY));
+    }
+
+    #[test]
+    fn monthly_due_times_keep_the_anchor_day() {
+        let interval = PaymentInterval::Monthly;
+        assert_eq!(interval.due_time(JAN_31_2024, 0), Some(JAN_31_2024));
+        assert_eq!(interval.due_time(JAN_31_2024, 1), Some(FEB_29_2024));
+        assert_eq!(interval.due_time(JAN_31_2024, 2), Some(MAR_31_2024));
+        assert_eq!(PaymentInterval::Yearly.due_time(FEB_29_2024, 1), Some(FEB_28_2025));
+    }
+
+    #[test]
+    fn periods_elapsed_handles_the_estimate_off_by_one() {
+        let interval = PaymentInterval::Monthly;
+        assert_eq!(interval.periods_elapsed(JAN_31_2024, JAN_31_2024 - 1), 0);
+        assert_eq!(interval.periods_elapsed(JAN_31_2024, JAN_31_2024), 1);
+
+        // February is one calendar month ahead, but the Feb 29 installment isn't due yet
+        assert_eq!(interval.periods_elapsed(JAN_31_2024, FEB_15_2024), 1);
+        assert_eq!(interval.periods_elapsed(JAN_31_2024, FEB_29_2024 - 1), 1);
+        assert_eq!(interval.periods_elapsed(JAN_31_2024, FEB_29_2024), 2);
+        assert_eq!(interval.periods_elapsed(JAN_31_2024, MAR_31_2024 - 1), 2);
+        assert_eq!(interval.periods_elapsed(JAN_31_2024, MAR_31_2024), 3);
+        assert_eq!(PaymentInterval::Quarterly.periods_elapsed(JAN_31_2024, APR_30_2024), 2);
+    }
+
+    #[test]
+    fn periods_elapsed_matches_due_times() {
+        for interval in [
+            PaymentInterval::Monthly,
+            PaymentInterval::Quarterly,
+            PaymentInterval::Weekly,
+            PaymentInterval::Custom { seconds: 3 * SECONDS_PER_DAY + 7 },
+        ] {
+            for index in 0..40 {
+                let due = interval.due_time(JAN_31_2024, index).unwrap();
+                assert_eq!(interval.periods_elapsed(JAN_31_2024, due - 1), index);
+                assert_eq!(interval.periods_elapsed(JAN_31_2024, due), index + 1);
+            }
+        }
+    }
+}
@@ -0,0 +1,556 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::recurring::unpack_token_account;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct PaymentStream {
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub stream_token_account: Pubkey, // Vault owned by this PDA
+    pub deposited: u64,
+    pub withdrawn: u64,
+    pub rate_per_second: u64,
+    pub start_time: i64,
+    pub end_time: i64, // When the deposit runs out at the current rate
+    pub cancelled_at: i64, // 0 while the stream is running
+    pub created_at: i64,
+    pub description: String,
+    pub bump: u8,
+}
+
+impl PaymentStream {
+    pub const MAX_SIZE: usize = 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 4 + 100 + 1; // ~257 bytes
+
+    // Rounded up
+    fn duration(deposited: u64, rate_per_second: u64) -> i64 {
+        let seconds = (deposited as u128).div_ceil(rate_per_second as u128);
+        seconds.min(i64::MAX as u128) as i64
+    }
+
+    // Frozen at cancellation
+    pub fn vested_amount(&self, current_time: i64) -> u64 {
+        let stop_time = if self.cancelled_at > 0 {
+            self.cancelled_at
+        } else {
+            current_time
+        };
+
+        let elapsed = stop_time.min(self.end_time).saturating_sub(self.start_time);
+        if elapsed <= 0 {
+            return 0;
+        }
+
+        let vested = (self.rate_per_second as u128).saturating_mul(elapsed as u128);
+
+        vested.min(self.deposited as u128) as u64
+    }
+
+    pub fn withdrawable_amount(&self, current_time: i64) -> u64 {
+        self.vested_amount(current_time).saturating_sub(self.withdrawn)
+    }
+
+    fn transfer_from_stream<'a>(
+        &self,
+        stream_account: &AccountInfo<'a>,
+        stream_token_account: &AccountInfo<'a>,
+        destination_token_account: &AccountInfo<'a>,
+        token_program: &AccountInfo<'a>,
+        amount: u64,
+    ) -> ProgramResult {
+        let transfer_instruction = spl_token_2022::instruction::transfer_checked(
+            token_program.key,
+            stream_token_account.key,
+            &spl_token_2022::id(),
+            destination_token_account.key,
+            stream_account.key, // PDA authority
+            &[],
+            amount,
+            6, // PYUSD decimals
+        )?;
+
+        invoke_signed(
+            &transfer_instruction,
+            &[
+                stream_token_account.clone(),
+                destination_token_account.clone(),
+                stream_account.clone(),
+                token_program.clone(),
+            ],
+            &[&[
+                b"stream",
+                self.sender.as_ref(),
+                &self.created_at.to_le_bytes(),
+                &[self.bump],
+            ]],
+        )
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct CreateStreamArgs {
+    pub recipient: Pubkey,
+    pub deposit: u64,
+    pub rate_per_second: u64,
+    pub start_time: i64, // 0 starts streaming immediately
+    pub description: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct TopUpStreamArgs {
+    pub amount: u64,
+}
+
+pub fn process_create_stream(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let stream_account = next_account_info(accounts_iter)?;
+    let sender = next_account_info(accounts_iter)?;
+    let sender_token_account = next_account_info(accounts_iter)?;
+    let stream_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !sender.is_signer {
+        msg!("Sender must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let args = CreateStreamArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    if args.deposit == 0 {
+        msg!("Deposit must be greater than 0");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if args.rate_per_second == 0 {
+        msg!("Rate per second must be greater than 0");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let clock = Clock::get()?;
+    let current_time = clock.unix_timestamp;
+
+    let start_time = if args.start_time == 0 {
+        current_time
+    } else {
+        args.start_time
+    };
+
+    if start_time < current_time {
+        msg!("Start time cannot be in the past");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let end_time = start_time
+        .checked_add(PaymentStream::duration(args.deposit, args.rate_per_second))
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    // Derive PDA
+    let (stream_pda, bump) = Pubkey::find_program_address(
+        &[b"stream", sender.key.as_ref(), &current_time.to_le_bytes()],
+        program_id,
+    );
+
+    if stream_pda != *stream_account.key {
+        msg!("Invalid stream PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // The deposit is held in a token account owned by the stream PDA
+    let vault = unpack_token_account(stream_token_account)?;
+    if vault.owner != *stream_account.key {
+        msg!("Stream token account must be owned by the stream PDA");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Create stream account
+    let rent = Rent::get()?;
+    let space = PaymentStream::MAX_SIZE;
+    let lamports = rent.minimum_balance(space);
+
+    invoke_signed(
+        &system_instruction::create_account(
+            sender.key,
+            stream_account.key,
+            lamports,
+            space as u64,
+            program_id,
+        ),
+        &[sender.clone(), stream_account.clone(), system_program.clone()],
+        &[&[
+            b"stream",
+            sender.key.as_ref(),
+            &current_time.to_le_bytes(),
+            &[bump],
+        ]],
+    )?;
+
+    // Deposit the full amount up front
+    let transfer_instruction = spl_token_2022::instruction::transfer_checked(
+        token_program.key,
+        sender_token_account.key,
+        &spl_token_2022::id(),
+        stream_token_account.key,
+        sender.key,
+        &[],
+        args.deposit,
+        6, // PYUSD decimals
+    )?;
+
+    invoke(
+        &transfer_instruction,
+        &[
+            sender_token_account.clone(),
+            stream_token_account.clone(),
+            sender.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    let stream_data = PaymentStream {
+        sender: *sender.key,
+        recipient: args.recipient,
+        stream_token_account: *stream_token_account.key,
+        deposited: args.deposit,
+        withdrawn: 0,
+        rate_per_second: args.rate_per_second,
+        start_time,
+        end_time,
+        cancelled_at: 0,
+        created_at: current_time,
+        description: args.description,
+        bump,
+    };
+
+    stream_data.serialize(&mut &mut stream_account.data.borrow_mut()[..])?;
+
+    msg!("Payment stream created successfully!");
+    msg!("Stream PDA: {}", stream_account.key);
+    msg!("Rate per second: {}", args.rate_per_second);
+    msg!("Streaming from {} to {}", start_time, end_time);
+
+    Ok(())
+}
+
+pub fn process_withdraw_from_stream(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let stream_account = next_account_info(accounts_iter)?;
+    let recipient = next_account_info(accounts_iter)?;
+    let stream_token_account = next_account_info(accounts_iter)?;
+    let recipient_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if !recipient.is_signer {
+        msg!("Recipient must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if stream_account.owner != program_id {
+        msg!("Stream must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut stream_data = PaymentStream::try_from_slice(&stream_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if stream_data.recipient != *recipient.key {
+        msg!("Only recipient can withdraw from stream");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stream_data.stream_token_account != *stream_token_account.key {
+        msg!("Invalid stream token account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let clock = Clock::get()?;
+    let amount = stream_data.withdrawable_amount(clock.unix_timestamp);
+
+    if amount == 0 {
+        msg!("Nothing to withdraw");
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    stream_data.transfer_from_stream(
+        stream_account,
+        stream_token_account,
+        recipient_token_account,
+        token_program,
+        amount,
+    )?;
+
+    stream_data.withdrawn = stream_data.withdrawn.saturating_add(amount);
+    stream_data.serialize(&mut &mut stream_account.data.borrow_mut()[..])?;
+
+    msg!("Withdrawn from stream: {}", amount);
+    msg!("Total withdrawn: {}/{}", stream_data.withdrawn, stream_data.deposited);
+
+    Ok(())
+}
+
+// Extends the end time at the same rate
+pub fn process_top_up_stream(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let stream_account = next_account_info(accounts_iter)?;
+    let sender = next_account_info(accounts_iter)?;
+    let sender_token_account = next_account_info(accounts_iter)?;
+    let stream_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if !sender.is_signer {
+        msg!("Sender must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if stream_account.owner != program_id {
+        msg!("Stream must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let args = TopUpStreamArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    if args.amount == 0 {
+        msg!("Top-up amount must be greater than 0");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut stream_data = PaymentStream::try_from_slice(&stream_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if stream_data.sender != *sender.key {
+        msg!("Only sender can top up stream");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stream_data.stream_token_account != *stream_token_account.key {
+        msg!("Invalid stream token account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stream_data.cancelled_at > 0 {
+        msg!("Stream has been cancelled");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // A depleted stream would retroactively vest the gap, so it can't be revived
+    let clock = Clock::get()?;
+    if clock.unix_timestamp >= stream_data.end_time {
+        msg!("Stream has already ended");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let deposited = stream_data
+        .deposited
+        .checked_add(args.amount)
+        .ok_or(ProgramError::InvalidArgument)?;
+    let end_time = stream_data
+        .start_time
+        .checked_add(PaymentStream::duration(deposited, stream_data.rate_per_second))
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    let transfer_instruction = spl_token_2022::instruction::transfer_checked(
+        token_program.key,
+        sender_token_account.key,
+        &spl_token_2022::id(),
+        stream_token_account.key,
+        sender.key,
+        &[],
+        args.amount,
+        6, // PYUSD decimals
+    )?;
+
+    invoke(
+        &transfer_instruction,
+        &[
+            sender_token_account.clone(),
+            stream_token_account.clone(),
+            sender.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    stream_data.deposited = deposited;
+    stream_data.end_time = end_time;
+    stream_data.serialize(&mut &mut stream_account.data.borrow_mut()[..])?;
+
+    msg!("Stream topped up by: {}", args.amount);
+    msg!("Stream now ends at: {}", end_time);
+
+    Ok(())
+}
+
+// Recipient gets everything vested, the sender the rest
+pub fn process_cancel_stream(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let stream_account = next_account_info(accounts_iter)?;
+    let sender = next_account_info(accounts_iter)?;
+    let stream_token_account = next_account_info(accounts_iter)?;
+    let sender_token_account = next_account_info(accounts_iter)?;
+    let recipient_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if !sender.is_signer {
+        msg!("Sender must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if stream_account.owner != program_id {
+        msg!("Stream must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut stream_data = PaymentStream::try_from_slice(&stream_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if stream_data.sender != *sender.key {
+        msg!("Only sender can cancel stream");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stream_data.stream_token_account != *stream_token_account.key {
+        msg!("Invalid stream token account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stream_data.cancelled_at > 0 {
+        msg!("Stream already cancelled");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if unpack_token_account(recipient_token_account)?.owner != stream_data.recipient {
+        msg!("Recipient token account must belong to the recipient");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let clock = Clock::get()?;
+    let vested = stream_data.vested_amount(clock.unix_timestamp);
+    let owed_to_recipient = vested.saturating_sub(stream_data.withdrawn);
+    let refund = stream_data.deposited.saturating_sub(vested);
+
+    if owed_to_recipient > 0 {
+        stream_data.transfer_from_stream(
+            stream_account,
+            stream_token_account,
+            recipient_token_account,
+            token_program,
+            owed_to_recipient,
+        )?;
+    }
+
+    if refund > 0 {
+        stream_data.transfer_from_stream(
+            stream_account,
+            stream_token_account,
+            sender_token_account,
+            token_program,
+            refund,
+        )?;
+    }
+
+    stream_data.withdrawn = vested;
+    stream_data.cancelled_at = clock.unix_timestamp;
+    stream_data.serialize(&mut &mut stream_account.data.borrow_mut()[..])?;
+
+    msg!("Payment stream cancelled successfully!");
+    msg!("Paid to recipient: {}", owed_to_recipient);
+    msg!("Refunded to sender: {}", refund);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(deposited: u64, rate_per_second: u64, start_time: i64) -> PaymentStream {
+        PaymentStream {
+            sender: Pubkey::new_unique(),
+            recipient: Pubkey::new_unique(),
+            stream_token_account: Pubkey::new_unique(),
+            deposited,
+            withdrawn: 0,
+            rate_per_second,
+            start_time,
+            end_time: start_time + PaymentStream::duration(deposited, rate_per_second),
+            cancelled_at: 0,
+            created_at: start_time,
+            description: String::new(),
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn duration_rounds_up() {
+        assert_eq!(PaymentStream::duration(100, 10), 10);
+        assert_eq!(PaymentStream::duration(101, 10), 11);
+        assert_eq!(PaymentStream::duration(u64::MAX, 1), i64::MAX);
+    }
+
+    #[test]
+    fn vests_linearly_between_start_and_end() {
+        let stream = stream(1_000, 10, 1_000);
+        assert_eq!(stream.vested_amount(0), 0);
+        assert_eq!(stream.vested_amount(1_000), 0);
+        assert_eq!(stream.vested_amount(1_001), 10);
+        assert_eq!(stream.vested_amount(1_050), 500);
+        assert_eq!(stream.vested_amount(1_100), 1_000);
+        assert_eq!(stream.vested_amount(i64::MAX), 1_000);
+    }
+
+    #[test]
+    fn last_second_is_capped_at_the_deposit() {
+        let stream = stream(105, 10, 0);
+        assert_eq!(stream.end_time, 11);
+        assert_eq!(stream.vested_amount(10), 100);
+        assert_eq!(stream.vested_amount(11), 105);
+    }
+
+    #[test]
+    fn cancellation_freezes_vesting() {
+        let mut stream = stream(1_000, 10, 0);
+        stream.cancelled_at = 30;
+        assert_eq!(stream.vested_amount(30), 300);
+        assert_eq!(stream.vested_amount(90), 300);
+    }
+
+    #[test]
+    fn withdrawable_excludes_withdrawn() {
+        let mut stream = stream(1_000, 10, 0);
+        stream.withdrawn = 250;
+        assert_eq!(stream.withdrawable_amount(20), 0);
+        assert_eq!(stream.withdrawable_amount(40), 150);
+    }
+}
@@ -0,0 +1,547 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::recurring::{ensure_delegate_available, is_current_delegate, PaymentInterval};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SubscriptionPlan {
+    pub merchant: Pubkey,
+    pub merchant_token_account: Pubkey,
+    pub price: u64,
+    pub interval: PaymentInterval,
+    pub trial_seconds: i64,
+    pub max_subscribers: u32, // 0 = unlimited
+    pub subscriber_count: u32,
+    pub pending_price: u64,
+    pub price_effective_at: i64, // 0 when no price change is scheduled
+    pub created_at: i64,
+    pub name: String,
+    pub bump: u8,
+}
+
+impl SubscriptionPlan {
+    pub const MAX_SIZE: usize = 32 + 32 + 8 + 9 + 8 + 4 + 4 + 8 + 8 + 8 + 4 + 100 + 1; // ~226 bytes
+
+    pub const PRICE_CHANGE_NOTICE_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+    pub fn price_at(&self, due_time: i64) -> u64 {
+        if self.price_effective_at > 0 && due_time >= self.price_effective_at {
+            self.pending_price
+        } else {
+            self.price
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.max_subscribers > 0 && self.subscriber_count >= self.max_subscribers
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Subscription {
+    pub plan: Pubkey,
+    pub subscriber: Pubkey,
+    pub subscriber_token_account: Pubkey, // Delegated to this PDA
+    pub billing_start: i64, // First period comes due once the trial ends
+    pub next_period: u64,
+    pub last_payment_time: i64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl Subscription {
+    pub const MAX_SIZE: usize = 32 + 32 + 32 + 8 + 8 + 8 + 8 + 1; // ~129 bytes
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct CreateSubscriptionPlanArgs {
+    pub merchant_token_account: Pubkey,
+    pub price: u64,
+    pub interval: PaymentInterval,
+    pub trial_seconds: i64,
+    pub max_subscribers: u32,
+    pub name: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct UpdatePlanPriceArgs {
+    pub new_price: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SubscribeArgs {
+    pub allowance: u64, // Total the subscription may charge before re-approval
+}
+
+pub fn process_create_subscription_plan(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let plan_account = next_account_info(accounts_iter)?;
+    let merchant = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !merchant.is_signer {
+        msg!("Merchant must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let args = CreateSubscriptionPlanArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    if args.price == 0 {
+        msg!("Price must be greater than 0");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if !args.interval.is_valid() {
+        msg!("Invalid billing interval");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if args.trial_seconds < 0 {
+        msg!("Trial period cannot be negative");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let clock = Clock::get()?;
+    let current_time = clock.unix_timestamp;
+
+    // Derive PDA
+    let (plan_pda, bump) = Pubkey::find_program_address(
+        &[
+            b"subscription_plan",
+            merchant.key.as_ref(),
+            &current_time.to_le_bytes(),
+        ],
+        program_id,
+    );
+
+    if plan_pda != *plan_account.key {
+        msg!("Invalid subscription plan PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Create plan account
+    let rent = Rent::get()?;
+    let space = SubscriptionPlan::MAX_SIZE;
+    let lamports = rent.minimum_balance(space);
+
+    invoke_signed(
+        &system_instruction::create_account(
+            merchant.key,
+            plan_account.key,
+            lamports,
+            space as u64,
+            program_id,
+        ),
+        &[merchant.clone(), plan_account.clone(), system_program.clone()],
+        &[&[
+            b"subscription_plan",
+            merchant.key.as_ref(),
+            &current_time.to_le_bytes(),
+            &[bump],
+        ]],
+    )?;
+
+    let plan_data = SubscriptionPlan {
+        merchant: *merchant.key,
+        merchant_token_account: args.merchant_token_account,
+        price: args.price,
+        interval: args.interval,
+        trial_seconds: args.trial_seconds,
+        max_subscribers: args.max_subscribers,
+        subscriber_count: 0,
+        pending_price: 0,
+        price_effective_at: 0,
+        created_at: current_time,
+        name: args.name,
+        bump,
+    };
+
+    plan_data.serialize(&mut &mut plan_account.data.borrow_mut()[..])?;
+
+    msg!("Subscription plan created successfully!");
+    msg!("Plan PDA: {}", plan_account.key);
+    msg!("Price: {} every {:?}", plan_data.price, plan_data.interval);
+
+    Ok(())
+}
+
+// Periods due within the notice window keep the current price
+pub fn process_update_plan_price(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let plan_account = next_account_info(accounts_iter)?;
+    let merchant = next_account_info(accounts_iter)?;
+
+    if !merchant.is_signer {
+        msg!("Merchant must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if plan_account.owner != program_id {
+        msg!("Subscription plan must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let args = UpdatePlanPriceArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    if args.new_price == 0 {
+        msg!("Price must be greater than 0");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut plan_data = SubscriptionPlan::try_from_slice(&plan_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if plan_data.merchant != *merchant.key {
+        msg!("Only merchant can update plan price");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let clock = Clock::get()?;
+
+    // A change whose notice window has passed becomes the current price
+    if plan_data.price_effective_at > 0 && clock.unix_timestamp >= plan_data.price_effective_at {
+        plan_data.price = plan_data.pending_price;
+    }
+
+    plan_data.pending_price = args.new_price;
+    plan_data.price_effective_at = clock
+        .unix_timestamp
+        .checked_add(SubscriptionPlan::PRICE_CHANGE_NOTICE_SECONDS)
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    plan_data.serialize(&mut &mut plan_account.data.borrow_mut()[..])?;
+
+    msg!("Plan price change scheduled: {} -> {}", plan_data.price, args.new_price);
+    msg!("Effective from: {}", plan_data.price_effective_at);
+
+    Ok(())
+}
+
+pub fn process_subscribe(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let subscription_account = next_account_info(accounts_iter)?;
+    let subscriber = next_account_info(accounts_iter)?;
+    let subscriber_token_account = next_account_info(accounts_iter)?;
+    let plan_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !subscriber.is_signer {
+        msg!("Subscriber must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if plan_account.owner != program_id {
+        msg!("Subscription plan must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let args = SubscribeArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    if args.allowance == 0 {
+        msg!("Allowance must be greater than 0");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut plan_data = SubscriptionPlan::try_from_slice(&plan_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if plan_data.is_full() {
+        msg!("Subscription plan is full");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let clock = Clock::get()?;
+    let current_time = clock.unix_timestamp;
+
+    let billing_start = current_time
+        .checked_add(plan_data.trial_seconds)
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    // Derive PDA
+    let (subscription_pda, bump) = Pubkey::find_program_address(
+        &[
+            b"subscription",
+            plan_account.key.as_ref(),
+            subscriber.key.as_ref(),
+        ],
+        program_id,
+    );
+
+    if subscription_pda != *subscription_account.key {
+        msg!("Invalid subscription PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Create subscription account
+    let rent = Rent::get()?;
+    let space = Subscription::MAX_SIZE;
+    let lamports = rent.minimum_balance(space);
+
+    invoke_signed(
+        &system_instruction::create_account(
+            subscriber.key,
+            subscription_account.key,
+            lamports,
+            space as u64,
+            program_id,
+        ),
+        &[subscriber.clone(), subscription_account.clone(), system_program.clone()],
+        &[&[
+            b"subscription",
+            plan_account.key.as_ref(),
+            subscriber.key.as_ref(),
+            &[bump],
+        ]],
+    )?;
+
+    ensure_delegate_available(subscriber_token_account, subscription_account.key)?;
+
+    // Delegate the allowance to the subscription PDA so anyone can crank billing
+    let approve_instruction = spl_token_2022::instruction::approve(
+        token_program.key,
+        subscriber_token_account.key,
+        subscription_account.key,
+        subscriber.key,
+        &[],
+        args.allowance,
+    )?;
+
+    invoke(
+        &approve_instruction,
+        &[
+            subscriber_token_account.clone(),
+            subscription_account.clone(),
+            subscriber.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    let subscription_data = Subscription {
+        plan: *plan_account.key,
+        subscriber: *subscriber.key,
+        subscriber_token_account: *subscriber_token_account.key,
+        billing_start,
+        next_period: 0,
+        last_payment_time: 0,
+        created_at: current_time,
+        bump,
+    };
+
+    subscription_data.serialize(&mut &mut subscription_account.data.borrow_mut()[..])?;
+
+    plan_data.subscriber_count += 1;
+    plan_data.serialize(&mut &mut plan_account.data.borrow_mut()[..])?;
+
+    msg!("Subscribed successfully!");
+    msg!("Subscription PDA: {}", subscription_account.key);
+    msg!("Billing starts: {}", billing_start);
+
+    Ok(())
+}
+
+// Callable by anyone; missed periods are not charged retroactively
+pub fn process_charge_subscription(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let subscription_account = next_account_info(accounts_iter)?;
+    let plan_account = next_account_info(accounts_iter)?;
+    let subscriber_token_account = next_account_info(accounts_iter)?;
+    let merchant_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if subscription_account.owner != program_id || plan_account.owner != program_id {
+        msg!("Subscription and plan must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut subscription_data = Subscription::try_from_slice(&subscription_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    let plan_data = SubscriptionPlan::try_from_slice(&plan_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if subscription_data.plan != *plan_account.key {
+        msg!("Subscription does not belong to this plan");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if subscription_data.subscriber_token_account != *subscriber_token_account.key {
+        msg!("Invalid subscriber token account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if plan_data.merchant_token_account != *merchant_token_account.key {
+        msg!("Invalid merchant token account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let clock = Clock::get()?;
+    let elapsed = plan_data
+        .interval
+        .periods_elapsed(subscription_data.billing_start, clock.unix_timestamp);
+
+    if elapsed <= subscription_data.next_period {
+        msg!("Subscription not due yet");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let period = elapsed - 1;
+    if period > subscription_data.next_period {
+        msg!("Skipping {} unbilled periods", period - subscription_data.next_period);
+    }
+
+    let due_time = plan_data
+        .interval
+        .due_time(subscription_data.billing_start, period)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let amount = plan_data.price_at(due_time);
+
+    // Charge as delegate of the subscriber's token account
+    let transfer_instruction = spl_token_2022::instruction::transfer_checked(
+        token_program.key,
+        subscriber_token_account.key,
+        &spl_token_2022::id(),
+        merchant_token_account.key,
+        subscription_account.key, // PDA authority
+        &[],
+        amount,
+        6, // PYUSD decimals
+    )?;
+
+    invoke_signed(
+        &transfer_instruction,
+        &[
+            subscriber_token_account.clone(),
+            merchant_token_account.clone(),
+            subscription_account.clone(),
+            token_program.clone(),
+        ],
+        &[&[
+            b"subscription",
+            subscription_data.plan.as_ref(),
+            subscription_data.subscriber.as_ref(),
+            &[subscription_data.bump],
+        ]],
+    )?;
+
+    subscription_data.next_period = elapsed;
+    subscription_data.last_payment_time = clock.unix_timestamp;
+    subscription_data.serialize(&mut &mut subscription_account.data.borrow_mut()[..])?;
+
+    msg!("Subscription charged: {}", amount);
+    msg!("Billing period: {}", period);
+
+    Ok(())
+}
+
+pub fn process_unsubscribe(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let subscription_account = next_account_info(accounts_iter)?;
+    let subscriber = next_account_info(accounts_iter)?;
+    let subscriber_token_account = next_account_info(accounts_iter)?;
+    let plan_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if !subscriber.is_signer {
+        msg!("Subscriber must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if subscription_account.owner != program_id || plan_account.owner != program_id {
+        msg!("Subscription and plan must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let subscription_data = Subscription::try_from_slice(&subscription_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    let mut plan_data = SubscriptionPlan::try_from_slice(&plan_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if subscription_data.subscriber != *subscriber.key {
+        msg!("Only subscriber can unsubscribe");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if subscription_data.plan != *plan_account.key {
+        msg!("Subscription does not belong to this plan");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if subscription_data.subscriber_token_account != *subscriber_token_account.key {
+        msg!("Invalid subscriber token account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Revoke the remaining allowance, unless another approval replaced it
+    if is_current_delegate(subscriber_token_account, subscription_account.key)? {
+        let revoke_instruction = spl_token_2022::instruction::revoke(
+            token_program.key,
+            subscriber_token_account.key,
+            subscriber.key,
+            &[],
+        )?;
+
+        invoke(
+            &revoke_instruction,
+            &[
+                subscriber_token_account.clone(),
+                subscriber.clone(),
+                token_program.clone(),
+            ],
+        )?;
+    } else {
+        msg!("Token account no longer delegates to this subscription, nothing to revoke");
+    }
+
+    plan_data.subscriber_count = plan_data.subscriber_count.saturating_sub(1);
+    plan_data.serialize(&mut &mut plan_account.data.borrow_mut()[..])?;
+
+    // Close the subscription account, returning its rent to the subscriber
+    let lamports = subscription_account.lamports();
+    **subscription_account.try_borrow_mut_lamports()? -= lamports;
+    **subscriber.try_borrow_mut_lamports()? += lamports;
+    subscription_account.data.borrow_mut().fill(0);
+
+    msg!("Unsubscribed successfully!");
+    msg!("Periods billed: {}", subscription_data.next_period);
+
+    Ok(())
+}
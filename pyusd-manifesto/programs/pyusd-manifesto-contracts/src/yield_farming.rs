@@ -3,6 +3,7 @@ use solana_program::{
     account_info::{next_account_info, AccountInfo},
     clock::Clock,
     entrypoint::ProgramResult,
+    log::sol_log_data,
     msg,
     program::{invoke, invoke_signed},
     program_error::ProgramError,
@@ -12,6 +13,11 @@ use solana_program::{
     sysvar::Sysvar,
 };
 
+use spl_token_2022::{extension::StateWithExtensions, state::Mint};
+
+use crate::recurring::unpack_token_account;
+
+// Lock presets for the default lock table
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
 pub enum LockPeriod {
     None,
@@ -29,101 +35,370 @@ impl LockPeriod {
             LockPeriod::OneEightyDays => 180 * 24 * 60 * 60,
         }
     }
+}
 
-    pub fn multiplier(&self) -> u64 {
-        match self {
-            LockPeriod::None => 100,        // 1.0x (100%)
-            LockPeriod::ThirtyDays => 130,  // 1.3x (130%)
-            LockPeriod::NinetyDays => 170,  // 1.7x (170%)
-            LockPeriod::OneEightyDays => 250, // 2.5x (250%)
-        }
-    }
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub struct RewardTier {
+    pub min_amount: u64,
+    pub apy_bps: u16, // Base APY, 1% = 100
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub struct LockTier {
+    pub duration_seconds: i64, // Positions reference their tier by duration
+    pub multiplier_bps: u16,   // 1.0x = 10000
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct StakingPool {
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey, // Default pubkey when no transfer is pending
     pub mint: Pubkey,
+    pub reward_mint: Pubkey, // May differ from the staked mint
+    pub reward_decimals: u8,
+    pub stake_vault: Pubkey,  // Holds staked principal, owned by this PDA
+    pub reward_vault: Pubkey, // Holds reward funding, owned by this PDA
     pub reward_rate_per_second: u64,
     pub total_staked: u64,
+    pub max_total_staked: u64, // 0 = no cap
+    pub is_paused: bool, // Blocks new deposits only
+    pub early_unstake_penalty_bps: u16, // Principal penalty at the start of a lock
+    pub unstake_cooldown_seconds: i64, // 0 allows direct unstaking
+    pub total_unbonding: u64, // Principal in cooldown, still held in the stake vault
+    pub total_pending_rewards: u64, // Settled rewards owed to stakers but not yet paid
+    pub tiers: Vec<RewardTier>,     // Ascending by min_amount
+    pub lock_tiers: Vec<LockTier>,  // Ascending by duration
+    pub total_weight: u64, // Sum of stake reward weights
+    pub acc_reward_per_share: u128, // Rewards per unit of weight, scaled by ACC_PRECISION
+    pub acc_penalty_per_share: u128, // Redistributed principal per unit of weight, scaled by ACC_PRECISION
+    pub undistributed_penalties: u64, // Penalties held in the stake vault until someone has weight
+    pub last_update_time: i64,
     pub created_at: i64,
     pub bump: u8,
 }
 
 impl StakingPool {
-    pub const MAX_SIZE: usize = 32 + 8 + 8 + 8 + 1;
+    pub const MAX_SIZE: usize =
+        32 + 32 + 32 + 32 + 1 + 32 + 32 + 8 + 8 + 8 + 1 + 2 + 8 + 8 + 8
+        + (4 + 10 * Self::MAX_TIERS)
+        + (4 + 10 * Self::MAX_LOCK_TIERS)
+        + 8 + 16 + 16 + 8 + 8 + 8 + 1;
+
+    pub const ACC_PRECISION: u128 = 1_000_000_000_000;
+
+    pub const MAX_EARLY_UNSTAKE_PENALTY_BPS: u16 = 5000; // 50%
+
+    pub const MAX_UNSTAKE_COOLDOWN_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+    pub const MAX_TIERS: usize = 8;
+    pub const MAX_LOCK_TIERS: usize = 8;
+    pub const MAX_APY_BPS: u16 = 10000; // 100%
+    pub const MIN_MULTIPLIER_BPS: u16 = 10000; // 1.0x
+    pub const MAX_MULTIPLIER_BPS: u16 = 50000; // 5.0x
+    pub const MAX_LOCK_DURATION_SECONDS: i64 = 4 * 365 * 24 * 60 * 60;
+
+    // Default Bronze/Silver/Gold/Diamond tiers
+    pub fn default_tiers() -> Vec<RewardTier> {
+        vec![
+            RewardTier { min_amount: 100_000_000, apy_bps: 800 },     // >= 100 PYUSD (Bronze)
+            RewardTier { min_amount: 1_000_000_000, apy_bps: 1500 },  // >= 1,000 PYUSD (Silver)
+            RewardTier { min_amount: 10_000_000_000, apy_bps: 2500 }, // >= 10,000 PYUSD (Gold)
+            RewardTier { min_amount: 50_000_000_000, apy_bps: 4000 }, // >= 50,000 PYUSD (Diamond)
+        ]
+    }
+
+    // Default lock multipliers
+    pub fn default_lock_tiers() -> Vec<LockTier> {
+        vec![
+            LockTier { duration_seconds: LockPeriod::None.to_seconds(), multiplier_bps: 10000 }, // 1.0x
+            LockTier { duration_seconds: LockPeriod::ThirtyDays.to_seconds(), multiplier_bps: 13000 }, // 1.3x
+            LockTier { duration_seconds: LockPeriod::NinetyDays.to_seconds(), multiplier_bps: 17000 }, // 1.7x
+            LockTier { duration_seconds: LockPeriod::OneEightyDays.to_seconds(), multiplier_bps: 25000 }, // 2.5x
+        ]
+    }
+
+    pub fn validate_tiers(tiers: &[RewardTier]) -> bool {
+        !tiers.is_empty()
+            && tiers.len() <= Self::MAX_TIERS
+            && tiers.iter().all(|tier| tier.apy_bps <= Self::MAX_APY_BPS)
+            && tiers.windows(2).all(|pair| pair[0].min_amount < pair[1].min_amount)
+    }
+
+    pub fn validate_lock_tiers(lock_tiers: &[LockTier]) -> bool {
+        !lock_tiers.is_empty()
+            && lock_tiers.len() <= Self::MAX_LOCK_TIERS
+            && lock_tiers.iter().all(|tier| {
+                (0..=Self::MAX_LOCK_DURATION_SECONDS).contains(&tier.duration_seconds)
+                    && (Self::MIN_MULTIPLIER_BPS..=Self::MAX_MULTIPLIER_BPS).contains(&tier.multiplier_bps)
+            })
+            && lock_tiers
+                .windows(2)
+                .all(|pair| pair[0].duration_seconds < pair[1].duration_seconds)
+    }
+
+    // None if the pool doesn't offer this duration
+    pub fn lock_multiplier_bps(&self, lock_duration_seconds: i64) -> Option<u16> {
+        self.lock_tiers
+            .iter()
+            .find(|tier| tier.duration_seconds == lock_duration_seconds)
+            .map(|tier| tier.multiplier_bps)
+    }
+
+    // Highest tier reached, boosted by the lock multiplier
+    pub fn calculate_tier_apy(&self, amount: u64, lock_duration_seconds: i64) -> u64 {
+        let base_apy = self
+            .tiers
+            .iter()
+            .rev()
+            .find(|tier| amount >= tier.min_amount)
+            .map_or(0, |tier| tier.apy_bps as u64);
+
+        // Locks removed from the table after staking earn no boost
+        let multiplier = self
+            .lock_multiplier_bps(lock_duration_seconds)
+            .unwrap_or(Self::MIN_MULTIPLIER_BPS) as u64;
+
+        base_apy * multiplier / 10000
+    }
+
+    // Amount scaled by tier APY so tiers and locks still boost rewards
+    pub fn reward_weight(&self, amount: u64, lock_duration_seconds: i64) -> u64 {
+        let apy = self.calculate_tier_apy(amount, lock_duration_seconds);
+        ((amount as u128).saturating_mul(apy as u128) / 10000) as u64
+    }
+
+    pub fn is_valid_cooldown(seconds: i64) -> bool {
+        (0..=Self::MAX_UNSTAKE_COOLDOWN_SECONDS).contains(&seconds)
+    }
+
+    // Must run before any stake weight changes
+    pub fn update_rewards(&mut self, current_time: i64) {
+        let time_elapsed = current_time.saturating_sub(self.last_update_time);
+        if time_elapsed <= 0 {
+            return;
+        }
+
+        if self.total_weight > 0 {
+            let rewards = (self.reward_rate_per_second as u128).saturating_mul(time_elapsed as u128);
+            self.acc_reward_per_share = self.acc_reward_per_share.saturating_add(
+                rewards.saturating_mul(Self::ACC_PRECISION) / self.total_weight as u128,
+            );
+        }
+
+        self.last_update_time = current_time;
+        self.flush_penalties();
+    }
+
+    // Nothing left in either vault belongs to stakers
+    pub fn is_closable(&self) -> bool {
+        self.total_staked == 0
+            && self.total_unbonding == 0
+            && self.total_pending_rewards == 0
+            && self.undistributed_penalties == 0
+    }
+
+    // Penalties held in the stake vault, shared by weight
+    pub fn distribute_penalty(&mut self, penalty: u64) {
+        self.undistributed_penalties = self.undistributed_penalties.saturating_add(penalty);
+        self.flush_penalties();
+    }
+
+    fn flush_penalties(&mut self) {
+        if self.undistributed_penalties == 0 || self.total_weight == 0 {
+            return;
+        }
+
+        self.acc_penalty_per_share = self.acc_penalty_per_share.saturating_add(
+            (self.undistributed_penalties as u128).saturating_mul(Self::ACC_PRECISION)
+                / self.total_weight as u128,
+        );
+        self.undistributed_penalties = 0;
+    }
+
+    pub fn transfer_from_vault<'a>(
+        &self,
+        staking_pool_account: &AccountInfo<'a>,
+        vault: &AccountInfo<'a>,
+        destination: &AccountInfo<'a>,
+        token_program: &AccountInfo<'a>,
+        amount: u64,
+        decimals: u8,
+    ) -> ProgramResult {
+        let transfer_instruction = spl_token_2022::instruction::transfer_checked(
+            token_program.key,
+            vault.key,
+            &spl_token_2022::id(),
+            destination.key,
+            staking_pool_account.key, // PDA authority
+            &[],
+            amount,
+            decimals,
+        )?;
+
+        invoke_signed(
+            &transfer_instruction,
+            &[
+                vault.clone(),
+                destination.clone(),
+                staking_pool_account.clone(),
+                token_program.clone(),
+            ],
+            &[&[b"staking_pool", self.mint.as_ref(), &[self.bump]]],
+        )
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct PendingWithdrawal {
+    pub amount: u64,
+    pub available_at: i64,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct UserStake {
     pub user: Pubkey,
     pub staking_pool: Pubkey,
+    pub pool_created_at: i64, // Ties the position to this incarnation of the pool PDA
+    pub position_index: u32,
     pub amount: u64,
-    pub lock_period: LockPeriod,
+    pub lock_duration_seconds: i64, // Duration of the pool lock tier this position uses
     pub lock_end_time: i64,
     pub last_claim_time: i64,
     pub total_claimed: u64,
+    pub weight: u64, // Reward weight counted in the pool's total_weight
+    pub reward_debt: u128, // Weight * acc_reward_per_share already accounted for
+    pub penalty_debt: u128, // Weight * acc_penalty_per_share already accounted for
+    pub pending_rewards: u64, // Settled but not yet paid out
+    pub pending_withdrawals: Vec<PendingWithdrawal>, // Unstakes in cooldown
     pub created_at: i64,
     pub bump: u8,
 }
 
 impl UserStake {
-    pub const MAX_SIZE: usize = 32 + 32 + 8 + 1 + 8 + 8 + 8 + 8 + 1;
-
-    pub fn calculate_tier_apy(&self) -> u64 {
-        // Base APY based on stake amount (in basis points, 1% = 100)
-        let base_apy = if self.amount >= 50_000_000_000 {
-            // >= 50,000 PYUSD (Diamond)
-            4000 // 40%
-        } else if self.amount >= 10_000_000_000 {
-            // >= 10,000 PYUSD (Gold)
-            2500 // 25%
-        } else if self.amount >= 1_000_000_000 {
-            // >= 1,000 PYUSD (Silver)
-            1500 // 15%
-        } else if self.amount >= 100_000_000 {
-            // >= 100 PYUSD (Bronze)
-            800 // 8%
-        } else {
-            0
-        };
+    pub const MAX_PENDING_WITHDRAWALS: usize = 5;
+    pub const MAX_SIZE: usize =
+        32 + 32 + 8 + 4 + 8 + 8 + 8 + 8 + 8 + 8 + 16 + 16 + 8 + (4 + 16 * Self::MAX_PENDING_WITHDRAWALS) + 8 + 1;
 
-        // Apply lock period multiplier
-        (base_apy * self.lock_period.multiplier()) / 100
+    fn accumulated_rewards(&self, acc_reward_per_share: u128) -> u128 {
+        (self.weight as u128).saturating_mul(acc_reward_per_share) / StakingPool::ACC_PRECISION
     }
 
-    pub fn calculate_pending_rewards(&self, current_time: i64) -> u64 {
-        if self.amount == 0 {
-            return 0;
+    // Penalty shares use the reward accumulator math
+    fn accumulated_penalties(&self, acc_penalty_per_share: u128) -> u128 {
+        self.accumulated_rewards(acc_penalty_per_share)
+    }
+
+    pub fn calculate_pending_rewards(&self, acc_reward_per_share: u128) -> u64 {
+        let accrued = self
+            .accumulated_rewards(acc_reward_per_share)
+            .saturating_sub(self.reward_debt);
+
+        (self.pending_rewards as u128).saturating_add(accrued).min(u64::MAX as u128) as u64
+    }
+
+    // Also credits this stake's share of redistributed penalties
+    pub fn settle_rewards(&mut self, pool_data: &mut StakingPool) {
+        let pending_rewards = self.calculate_pending_rewards(pool_data.acc_reward_per_share);
+        pool_data.total_pending_rewards = pool_data
+            .total_pending_rewards
+            .saturating_add(pending_rewards - self.pending_rewards);
+        self.pending_rewards = pending_rewards;
+        self.reward_debt = self.accumulated_rewards(pool_data.acc_reward_per_share);
+
+        let penalties = self.accumulated_penalties(pool_data.acc_penalty_per_share);
+        let penalty_share = penalties.saturating_sub(self.penalty_debt) as u64;
+        self.penalty_debt = penalties;
+
+        // Credited principal earns rewards from now on
+        if penalty_share > 0 {
+            self.amount = self.amount.saturating_add(penalty_share);
+            pool_data.total_staked = pool_data.total_staked.saturating_add(penalty_share);
+            reweight_stake(pool_data, self);
         }
+    }
 
-        let time_elapsed = current_time.saturating_sub(self.last_claim_time);
-        if time_elapsed <= 0 {
+    // Decays linearly from penalty_bps at lock start to zero at lock end
+    pub fn early_unstake_penalty(&self, amount: u64, penalty_bps: u16, current_time: i64) -> u64 {
+        let lock_duration = self.lock_duration_seconds;
+        let remaining = self.lock_end_time.saturating_sub(current_time).min(lock_duration);
+        if lock_duration <= 0 || remaining <= 0 {
             return 0;
         }
 
-        let apy = self.calculate_tier_apy();
+        let penalty = (amount as u128)
+            .saturating_mul(penalty_bps as u128)
+            .saturating_mul(remaining as u128)
+            / (lock_duration as u128 * 10000);
+
+        penalty as u64
+    }
+
+}
+
+// Vault must hold `mint` and be owned by the pool PDA
+fn validate_vault(vault: &AccountInfo, staking_pool_account: &AccountInfo, mint: &Pubkey) -> ProgramResult {
+    let vault_data = unpack_token_account(vault)?;
 
-        // Calculate rewards: (amount * APY * time_elapsed) / (365 days * 10000)
-        // APY is in basis points (1% = 100)
-        let seconds_per_year = 365 * 24 * 60 * 60;
-        let rewards = (self.amount as u128)
-            .saturating_mul(apy as u128)
-            .saturating_mul(time_elapsed as u128)
-            / (seconds_per_year as u128 * 10000);
+    if vault_data.owner != *staking_pool_account.key {
+        msg!("Vault token account must be owned by the staking pool PDA");
+        return Err(ProgramError::InvalidAccountData);
+    }
 
-        rewards as u64
+    if vault_data.mint != *mint {
+        msg!("Vault token account holds the wrong mint");
+        return Err(ProgramError::InvalidAccountData);
     }
+
+    Ok(())
+}
+
+// Positions are user_stake PDAs at indices 0..position_count
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct UserStakeIndex {
+    pub user: Pubkey,
+    pub staking_pool: Pubkey,
+    pub position_count: u32,
+    pub bump: u8,
+}
+
+impl UserStakeIndex {
+    pub const MAX_SIZE: usize = 32 + 32 + 4 + 1;
+}
+
+// Rewards must be settled first
+fn reweight_stake(pool_data: &mut StakingPool, stake_data: &mut UserStake) {
+    let new_weight = pool_data.reward_weight(stake_data.amount, stake_data.lock_duration_seconds);
+    pool_data.total_weight = pool_data
+        .total_weight
+        .saturating_sub(stake_data.weight)
+        .saturating_add(new_weight);
+    stake_data.weight = new_weight;
+    stake_data.reward_debt = stake_data.accumulated_rewards(pool_data.acc_reward_per_share);
+    stake_data.penalty_debt = stake_data.accumulated_penalties(pool_data.acc_penalty_per_share);
+}
+
+fn emit_event<T: BorshSerialize>(event: &T) -> ProgramResult {
+    sol_log_data(&[&event.try_to_vec()?]);
+    Ok(())
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct InitializeStakingPoolArgs {
     pub reward_rate_per_second: u64,
+    pub early_unstake_penalty_bps: u16,
+    pub unstake_cooldown_seconds: i64,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct StakeArgs {
     pub amount: u64,
-    pub lock_period: LockPeriod,
+    pub lock_duration_seconds: i64, // Must be offered by the pool's lock table
+    pub position_index: u32, // Equal to the user's position count to open a new position
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ExtendLockArgs {
+    pub lock_duration_seconds: i64, // Same or longer than the position's current lock
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
@@ -131,6 +406,65 @@ pub struct UnstakeArgs {
     pub amount: u64,
 }
 
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct FundRewardsArgs {
+    pub amount: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct UpdateRewardRateArgs {
+    pub reward_rate_per_second: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetPoolPausedArgs {
+    pub paused: bool,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetMaxTotalStakedArgs {
+    pub max_total_staked: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetUnstakeCooldownArgs {
+    pub unstake_cooldown_seconds: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetEarlyUnstakePenaltyArgs {
+    pub early_unstake_penalty_bps: u16,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetTierTableArgs {
+    pub tiers: Vec<RewardTier>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetLockTableArgs {
+    pub lock_tiers: Vec<LockTier>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct TierTableUpdated {
+    pub staking_pool: Pubkey,
+    pub tiers: Vec<RewardTier>,
+    pub updated_at: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct LockTableUpdated {
+    pub staking_pool: Pubkey,
+    pub lock_tiers: Vec<LockTier>,
+    pub updated_at: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct TransferPoolAuthorityArgs {
+    pub new_authority: Pubkey,
+}
+
 pub fn process_initialize_staking_pool(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -142,6 +476,9 @@ pub fn process_initialize_staking_pool(
     let mint_account = next_account_info(accounts_iter)?;
     let authority = next_account_info(accounts_iter)?;
     let system_program = next_account_info(accounts_iter)?;
+    let stake_vault = next_account_info(accounts_iter)?;
+    let reward_vault = next_account_info(accounts_iter)?;
+    let reward_mint_account = next_account_info(accounts_iter)?;
 
     if !authority.is_signer {
         msg!("Authority must be a signer");
@@ -151,6 +488,16 @@ pub fn process_initialize_staking_pool(
     let args = InitializeStakingPoolArgs::try_from_slice(instruction_data)
         .map_err(|_| ProgramError::InvalidInstructionData)?;
 
+    if args.early_unstake_penalty_bps > StakingPool::MAX_EARLY_UNSTAKE_PENALTY_BPS {
+        msg!("Penalty cannot exceed {} bps", StakingPool::MAX_EARLY_UNSTAKE_PENALTY_BPS);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if !StakingPool::is_valid_cooldown(args.unstake_cooldown_seconds) {
+        msg!("Cooldown must be between 0 and {} seconds", StakingPool::MAX_UNSTAKE_COOLDOWN_SECONDS);
+        return Err(ProgramError::InvalidArgument);
+    }
+
     let clock = Clock::get()?;
 
     // Derive PDA
@@ -164,6 +511,19 @@ pub fn process_initialize_staking_pool(
         return Err(ProgramError::InvalidArgument);
     }
 
+    // Principal and rewards are kept apart so claims can never touch deposits
+    if stake_vault.key == reward_vault.key {
+        msg!("Stake and reward vaults must be different accounts");
+        return Err(ProgramError::InvalidArgument);
+    }
+    validate_vault(stake_vault, staking_pool_account, mint_account.key)?;
+    validate_vault(reward_vault, staking_pool_account, reward_mint_account.key)?;
+
+    let reward_decimals = {
+        let mint_data = reward_mint_account.data.borrow();
+        StateWithExtensions::<Mint>::unpack(&mint_data)?.base.decimals
+    };
+
     // Create staking pool account
     let rent = Rent::get()?;
     let space = StakingPool::MAX_SIZE;
@@ -183,9 +543,28 @@ pub fn process_initialize_staking_pool(
 
     // Initialize pool data
     let pool_data = StakingPool {
+        authority: *authority.key,
+        pending_authority: Pubkey::default(),
         mint: *mint_account.key,
+        reward_mint: *reward_mint_account.key,
+        reward_decimals,
+        stake_vault: *stake_vault.key,
+        reward_vault: *reward_vault.key,
         reward_rate_per_second: args.reward_rate_per_second,
         total_staked: 0,
+        max_total_staked: 0,
+        is_paused: false,
+        early_unstake_penalty_bps: args.early_unstake_penalty_bps,
+        unstake_cooldown_seconds: args.unstake_cooldown_seconds,
+        total_unbonding: 0,
+        total_pending_rewards: 0,
+        tiers: StakingPool::default_tiers(),
+        lock_tiers: StakingPool::default_lock_tiers(),
+        total_weight: 0,
+        acc_reward_per_share: 0,
+        acc_penalty_per_share: 0,
+        undistributed_penalties: 0,
+        last_update_time: clock.unix_timestamp,
         created_at: clock.unix_timestamp,
         bump,
     };
@@ -193,6 +572,8 @@ pub fn process_initialize_staking_pool(
     pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
 
     msg!("Staking pool initialized successfully!");
+    msg!("Staked mint: {}", mint_account.key);
+    msg!("Reward mint: {}", reward_mint_account.key);
 
     Ok(())
 }
@@ -211,6 +592,7 @@ pub fn process_stake(
     let pool_token_account = next_account_info(accounts_iter)?;
     let token_program = next_account_info(accounts_iter)?;
     let system_program = next_account_info(accounts_iter)?;
+    let user_stake_index_account = next_account_info(accounts_iter)?;
 
     if !user.is_signer {
         msg!("User must be a signer");
@@ -225,6 +607,85 @@ pub fn process_stake(
     // Load or create pool
     let mut pool_data = StakingPool::try_from_slice(&staking_pool_account.data.borrow())
         .map_err(|_| ProgramError::InvalidAccountData)?;
+    pool_data.update_rewards(clock.unix_timestamp);
+
+    if pool_data.stake_vault != *pool_token_account.key {
+        msg!("Invalid stake vault");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if pool_data.is_paused {
+        msg!("Staking pool is paused");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if pool_data.lock_multiplier_bps(args.lock_duration_seconds).is_none() {
+        msg!("Lock duration is not offered by this pool");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let new_total_staked = pool_data
+        .total_staked
+        .checked_add(args.amount)
+        .ok_or(ProgramError::InvalidArgument)?;
+    if pool_data.max_total_staked > 0 && new_total_staked > pool_data.max_total_staked {
+        msg!("Deposit exceeds the pool's max total staked");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Derive user stake index PDA
+    let (index_pda, index_bump) = Pubkey::find_program_address(
+        &[
+            b"user_stake_index",
+            staking_pool_account.key.as_ref(),
+            user.key.as_ref(),
+        ],
+        program_id,
+    );
+
+    if index_pda != *user_stake_index_account.key {
+        msg!("Invalid user stake index PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Load or create the user's position index
+    let mut index_data = if user_stake_index_account.data_len() == 0 {
+        let rent = Rent::get()?;
+        let space = UserStakeIndex::MAX_SIZE;
+        let lamports = rent.minimum_balance(space);
+
+        invoke_signed(
+            &system_instruction::create_account(
+                user.key,
+                user_stake_index_account.key,
+                lamports,
+                space as u64,
+                program_id,
+            ),
+            &[user.clone(), user_stake_index_account.clone(), system_program.clone()],
+            &[&[
+                b"user_stake_index",
+                staking_pool_account.key.as_ref(),
+                user.key.as_ref(),
+                &[index_bump],
+            ]],
+        )?;
+
+        UserStakeIndex {
+            user: *user.key,
+            staking_pool: *staking_pool_account.key,
+            position_count: 0,
+            bump: index_bump,
+        }
+    } else {
+        UserStakeIndex::try_from_slice(&user_stake_index_account.data.borrow())
+            .map_err(|_| ProgramError::InvalidAccountData)?
+    };
+
+    if args.position_index > index_data.position_count {
+        msg!("Position index out of range");
+        return Err(ProgramError::InvalidArgument);
+    }
 
     // Derive user stake PDA
     let (stake_pda, bump) = Pubkey::find_program_address(
@@ -232,6 +693,7 @@ pub fn process_stake(
             b"user_stake",
             staking_pool_account.key.as_ref(),
             user.key.as_ref(),
+            &args.position_index.to_le_bytes(),
         ],
         program_id,
     );
@@ -241,8 +703,8 @@ pub fn process_stake(
         return Err(ProgramError::InvalidArgument);
     }
 
-    // Check if stake account exists
-    let is_new_stake = user_stake_account.data_len() == 0;
+    // Opening the next index creates a new position
+    let is_new_stake = args.position_index == index_data.position_count;
 
     if is_new_stake {
         // Create new stake account
@@ -263,42 +725,63 @@ pub fn process_stake(
                 b"user_stake",
                 staking_pool_account.key.as_ref(),
                 user.key.as_ref(),
+                &args.position_index.to_le_bytes(),
                 &[bump],
             ]],
         )?;
 
         // Initialize stake data
-        let lock_end_time = clock.unix_timestamp + args.lock_period.to_seconds();
+        let lock_end_time = clock.unix_timestamp + args.lock_duration_seconds;
 
-        let stake_data = UserStake {
+        let mut stake_data = UserStake {
             user: *user.key,
             staking_pool: *staking_pool_account.key,
+            pool_created_at: pool_data.created_at,
+            position_index: args.position_index,
             amount: args.amount,
-            lock_period: args.lock_period,
+            lock_duration_seconds: args.lock_duration_seconds,
             lock_end_time,
             last_claim_time: clock.unix_timestamp,
             total_claimed: 0,
+            weight: 0,
+            reward_debt: 0,
+            penalty_debt: 0,
+            pending_rewards: 0,
+            pending_withdrawals: Vec::new(),
             created_at: clock.unix_timestamp,
             bump,
         };
+        reweight_stake(&mut pool_data, &mut stake_data);
 
         stake_data.serialize(&mut &mut user_stake_account.data.borrow_mut()[..])?;
+
+        index_data.position_count += 1;
+        index_data.serialize(&mut &mut user_stake_index_account.data.borrow_mut()[..])?;
     } else {
-        // Update existing stake
+        // Add to an existing position
         let mut stake_data = UserStake::try_from_slice(&user_stake_account.data.borrow())
             .map_err(|_| ProgramError::InvalidAccountData)?;
 
-        // Claim any pending rewards first
-        let pending = stake_data.calculate_pending_rewards(clock.unix_timestamp);
-        if pending > 0 {
-            stake_data.total_claimed = stake_data.total_claimed.saturating_add(pending);
+        if stake_data.pool_created_at != pool_data.created_at {
+            msg!("Position belongs to a closed pool, open a new position instead");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Other lock periods go in their own position instead of relocking this one
+        if stake_data.lock_duration_seconds != args.lock_duration_seconds {
+            msg!("Lock duration differs from the position, open a new position instead");
+            return Err(ProgramError::InvalidArgument);
         }
 
+        // Settle rewards earned at the old weight first
+        stake_data.settle_rewards(&mut pool_data);
+
         // Update stake
         stake_data.amount = stake_data.amount.saturating_add(args.amount);
-        stake_data.lock_period = args.lock_period;
-        stake_data.lock_end_time = clock.unix_timestamp + args.lock_period.to_seconds();
-        stake_data.last_claim_time = clock.unix_timestamp;
+        stake_data.lock_end_time = stake_data
+            .lock_end_time
+            .max(clock.unix_timestamp + args.lock_duration_seconds);
+        reweight_stake(&mut pool_data, &mut stake_data);
 
         stake_data.serialize(&mut &mut user_stake_account.data.borrow_mut()[..])?;
     }
@@ -330,6 +813,7 @@ pub fn process_stake(
     pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
 
     msg!("Staked successfully!");
+    msg!("Position index: {}", args.position_index);
 
     Ok(())
 }
@@ -344,7 +828,7 @@ pub fn process_claim_rewards(
     let staking_pool_account = next_account_info(accounts_iter)?;
     let user_stake_account = next_account_info(accounts_iter)?;
     let user = next_account_info(accounts_iter)?;
-    let pool_token_account = next_account_info(accounts_iter)?;
+    let reward_vault = next_account_info(accounts_iter)?;
     let user_token_account = next_account_info(accounts_iter)?;
     let token_program = next_account_info(accounts_iter)?;
 
@@ -356,8 +840,9 @@ pub fn process_claim_rewards(
     let clock = Clock::get()?;
 
     // Load pool
-    let pool_data = StakingPool::try_from_slice(&staking_pool_account.data.borrow())
+    let mut pool_data = StakingPool::try_from_slice(&staking_pool_account.data.borrow())
         .map_err(|_| ProgramError::InvalidAccountData)?;
+    pool_data.update_rewards(clock.unix_timestamp);
 
     // Load stake
     let mut stake_data = UserStake::try_from_slice(&user_stake_account.data.borrow())
@@ -368,43 +853,64 @@ pub fn process_claim_rewards(
         return Err(ProgramError::InvalidAccountData);
     }
 
+    if stake_data.staking_pool != *staking_pool_account.key
+        || stake_data.pool_created_at != pool_data.created_at
+    {
+        msg!("Stake account does not belong to this pool");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if pool_data.reward_vault != *reward_vault.key {
+        msg!("Invalid reward vault");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let user_reward_account = unpack_token_account(user_token_account)?;
+    if user_reward_account.mint != pool_data.reward_mint || user_reward_account.owner != *user.key {
+        msg!("User token account must be the user's account for the reward mint");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
     // Calculate rewards
-    let rewards = stake_data.calculate_pending_rewards(clock.unix_timestamp);
+    stake_data.settle_rewards(&mut pool_data);
 
-    if rewards == 0 {
+    if stake_data.pending_rewards == 0 {
         msg!("No rewards to claim");
         return Err(ProgramError::InvalidAccountData);
     }
 
+    // Pay what the reward vault can cover; the rest stays owed
+    let available = unpack_token_account(reward_vault)?.amount;
+    let rewards = stake_data.pending_rewards.min(available);
+
+    if rewards == 0 {
+        msg!("Reward vault is empty");
+        return Err(ProgramError::InsufficientFunds);
+    }
+
     // Transfer rewards using pool PDA
-    let transfer_instruction = spl_token_2022::instruction::transfer_checked(
-        token_program.key,
-        pool_token_account.key,
-        &spl_token_2022::id(),
-        user_token_account.key,
-        staking_pool_account.key, // PDA authority
-        &[],
+    pool_data.transfer_from_vault(
+        staking_pool_account,
+        reward_vault,
+        user_token_account,
+        token_program,
         rewards,
-        6,
-    )?;
-
-    invoke_signed(
-        &transfer_instruction,
-        &[
-            pool_token_account.clone(),
-            user_token_account.clone(),
-            staking_pool_account.clone(),
-            token_program.clone(),
-        ],
-        &[&[b"staking_pool", pool_data.mint.as_ref(), &[pool_data.bump]]],
+        pool_data.reward_decimals,
     )?;
 
     // Update stake
+    stake_data.pending_rewards -= rewards;
+    pool_data.total_pending_rewards = pool_data.total_pending_rewards.saturating_sub(rewards);
     stake_data.total_claimed = stake_data.total_claimed.saturating_add(rewards);
     stake_data.last_claim_time = clock.unix_timestamp;
     stake_data.serialize(&mut &mut user_stake_account.data.borrow_mut()[..])?;
 
+    pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
+
     msg!("Rewards claimed: {}", rewards);
+    if stake_data.pending_rewards > 0 {
+        msg!("Rewards still owed: {}", stake_data.pending_rewards);
+    }
 
     Ok(())
 }
@@ -436,6 +942,7 @@ pub fn process_unstake(
     // Load pool
     let mut pool_data = StakingPool::try_from_slice(&staking_pool_account.data.borrow())
         .map_err(|_| ProgramError::InvalidAccountData)?;
+    pool_data.update_rewards(clock.unix_timestamp);
 
     // Load stake
     let mut stake_data = UserStake::try_from_slice(&user_stake_account.data.borrow())
@@ -446,9 +953,26 @@ pub fn process_unstake(
         return Err(ProgramError::InvalidAccountData);
     }
 
+    if stake_data.staking_pool != *staking_pool_account.key
+        || stake_data.pool_created_at != pool_data.created_at
+    {
+        msg!("Stake account does not belong to this pool");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if pool_data.stake_vault != *pool_token_account.key {
+        msg!("Invalid stake vault");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if pool_data.unstake_cooldown_seconds > 0 {
+        msg!("Pool has an unstake cooldown, use request unstake instead");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
     // Check lock period
     if clock.unix_timestamp < stake_data.lock_end_time {
-        msg!("Stake is still locked");
+        msg!("Stake is still locked, use early unstake to withdraw with a penalty");
         return Err(ProgramError::InvalidAccountData);
     }
 
@@ -458,30 +982,19 @@ pub fn process_unstake(
     }
 
     // Transfer staked tokens back
-    let transfer_instruction = spl_token_2022::instruction::transfer_checked(
-        token_program.key,
-        pool_token_account.key,
-        &spl_token_2022::id(),
-        user_token_account.key,
-        staking_pool_account.key,
-        &[],
+    pool_data.transfer_from_vault(
+        staking_pool_account,
+        pool_token_account,
+        user_token_account,
+        token_program,
         args.amount,
         6,
     )?;
 
-    invoke_signed(
-        &transfer_instruction,
-        &[
-            pool_token_account.clone(),
-            user_token_account.clone(),
-            staking_pool_account.clone(),
-            token_program.clone(),
-        ],
-        &[&[b"staking_pool", pool_data.mint.as_ref(), &[pool_data.bump]]],
-    )?;
-
-    // Update stake
+    // Update stake, keeping rewards earned so far claimable
+    stake_data.settle_rewards(&mut pool_data);
     stake_data.amount = stake_data.amount.saturating_sub(args.amount);
+    reweight_stake(&mut pool_data, &mut stake_data);
     stake_data.serialize(&mut &mut user_stake_account.data.borrow_mut()[..])?;
 
     // Update pool
@@ -503,6 +1016,9 @@ pub fn process_compound_rewards(
     let staking_pool_account = next_account_info(accounts_iter)?;
     let user_stake_account = next_account_info(accounts_iter)?;
     let user = next_account_info(accounts_iter)?;
+    let reward_vault = next_account_info(accounts_iter)?;
+    let stake_vault = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
 
     if !user.is_signer {
         msg!("User must be a signer");
@@ -514,6 +1030,7 @@ pub fn process_compound_rewards(
     // Load pool
     let mut pool_data = StakingPool::try_from_slice(&staking_pool_account.data.borrow())
         .map_err(|_| ProgramError::InvalidAccountData)?;
+    pool_data.update_rewards(clock.unix_timestamp);
 
     // Load stake
     let mut stake_data = UserStake::try_from_slice(&user_stake_account.data.borrow())
@@ -524,22 +1041,1114 @@ pub fn process_compound_rewards(
         return Err(ProgramError::InvalidAccountData);
     }
 
-    // Calculate rewards
-    let rewards = stake_data.calculate_pending_rewards(clock.unix_timestamp);
+    if stake_data.staking_pool != *staking_pool_account.key
+        || stake_data.pool_created_at != pool_data.created_at
+    {
+        msg!("Stake account does not belong to this pool");
+        return Err(ProgramError::InvalidAccountData);
+    }
 
-    if rewards == 0 {
-        msg!("No rewards to compound");
+    if pool_data.reward_vault != *reward_vault.key {
+        msg!("Invalid reward vault");
         return Err(ProgramError::InvalidAccountData);
     }
 
-    // Add rewards to stake amount
-    stake_data.amount = stake_data.amount.saturating_add(rewards);
-    stake_data.total_claimed = stake_data.total_claimed.saturating_add(rewards);
+    if pool_data.stake_vault != *stake_vault.key {
+        msg!("Invalid stake vault");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if pool_data.reward_mint != pool_data.mint {
+        msg!("Rewards paid in a different mint cannot be compounded");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Compounding is a deposit, so the pool's deposit controls apply
+    if pool_data.is_paused {
+        msg!("Staking pool is paused");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Calculate rewards
+    stake_data.settle_rewards(&mut pool_data);
+
+    if stake_data.pending_rewards == 0 {
+        msg!("No rewards to compound");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Compound only what the reward vault can cover; the rest stays owed
+    let available = unpack_token_account(reward_vault)?.amount;
+    let rewards = stake_data.pending_rewards.min(available);
+
+    if rewards == 0 {
+        msg!("Reward vault is empty");
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    let new_total_staked = pool_data
+        .total_staked
+        .checked_add(rewards)
+        .ok_or(ProgramError::InvalidArgument)?;
+    if pool_data.max_total_staked > 0 && new_total_staked > pool_data.max_total_staked {
+        msg!("Compounding exceeds the pool's max total staked, claim instead");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Move the rewards into principal; they join the position under its existing lock
+    pool_data.transfer_from_vault(
+        staking_pool_account,
+        reward_vault,
+        stake_vault,
+        token_program,
+        rewards,
+        pool_data.reward_decimals,
+    )?;
+
+    // Add rewards to stake amount
+    stake_data.amount = stake_data.amount.saturating_add(rewards);
+    stake_data.pending_rewards -= rewards;
+    pool_data.total_pending_rewards = pool_data.total_pending_rewards.saturating_sub(rewards);
+    stake_data.total_claimed = stake_data.total_claimed.saturating_add(rewards);
     stake_data.last_claim_time = clock.unix_timestamp;
+    reweight_stake(&mut pool_data, &mut stake_data);
     stake_data.serialize(&mut &mut user_stake_account.data.borrow_mut()[..])?;
 
+    pool_data.total_staked = pool_data.total_staked.saturating_add(rewards);
+    pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
+
     msg!("Rewards compounded: {}", rewards);
     msg!("New stake amount: {}", stake_data.amount);
 
     Ok(())
 }
+
+// Claims and compounds only draw from the reward vault
+pub fn process_fund_rewards(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staking_pool_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let authority_token_account = next_account_info(accounts_iter)?;
+    let reward_vault = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    let args = FundRewardsArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    if args.amount == 0 {
+        msg!("Funding amount must be greater than 0");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let pool_data = load_pool_as_authority(program_id, staking_pool_account, authority)?;
+
+    if pool_data.reward_vault != *reward_vault.key {
+        msg!("Invalid reward vault");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let transfer_instruction = spl_token_2022::instruction::transfer_checked(
+        token_program.key,
+        authority_token_account.key,
+        &spl_token_2022::id(),
+        reward_vault.key,
+        authority.key,
+        &[],
+        args.amount,
+        pool_data.reward_decimals,
+    )?;
+
+    invoke(
+        &transfer_instruction,
+        &[
+            authority_token_account.clone(),
+            reward_vault.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    msg!("Rewards funded: {}", args.amount);
+
+    Ok(())
+}
+
+fn load_pool_as_authority(
+    program_id: &Pubkey,
+    staking_pool_account: &AccountInfo,
+    authority: &AccountInfo,
+) -> Result<StakingPool, ProgramError> {
+    if !authority.is_signer {
+        msg!("Authority must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if staking_pool_account.owner != program_id {
+        msg!("Staking pool must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let pool_data = StakingPool::try_from_slice(&staking_pool_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if pool_data.authority != *authority.key {
+        msg!("Only the pool authority can perform this action");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(pool_data)
+}
+
+pub fn process_update_reward_rate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staking_pool_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+
+    let args = UpdateRewardRateArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let mut pool_data = load_pool_as_authority(program_id, staking_pool_account, authority)?;
+
+    // Rewards up to now accrue at the old rate
+    let clock = Clock::get()?;
+    pool_data.update_rewards(clock.unix_timestamp);
+
+    let old_rate = pool_data.reward_rate_per_second;
+    pool_data.reward_rate_per_second = args.reward_rate_per_second;
+    pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
+
+    msg!("Reward rate updated: {} -> {}", old_rate, args.reward_rate_per_second);
+
+    Ok(())
+}
+
+// Claims and withdrawals are unaffected
+pub fn process_set_pool_paused(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staking_pool_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+
+    let args = SetPoolPausedArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let mut pool_data = load_pool_as_authority(program_id, staking_pool_account, authority)?;
+
+    pool_data.is_paused = args.paused;
+    pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
+
+    if args.paused {
+        msg!("Staking pool paused");
+    } else {
+        msg!("Staking pool unpaused");
+    }
+
+    Ok(())
+}
+
+pub fn process_set_max_total_staked(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staking_pool_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+
+    let args = SetMaxTotalStakedArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let mut pool_data = load_pool_as_authority(program_id, staking_pool_account, authority)?;
+
+    pool_data.max_total_staked = args.max_total_staked;
+    pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
+
+    msg!("Max total staked set to: {}", args.max_total_staked);
+    if args.max_total_staked > 0 && pool_data.total_staked > args.max_total_staked {
+        msg!("Pool is above the new cap, new deposits are blocked");
+    }
+
+    Ok(())
+}
+
+// New authority must accept before control changes
+pub fn process_transfer_pool_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staking_pool_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+
+    let args = TransferPoolAuthorityArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let mut pool_data = load_pool_as_authority(program_id, staking_pool_account, authority)?;
+
+    pool_data.pending_authority = args.new_authority;
+    pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
+
+    msg!("Pool authority transfer proposed to: {}", args.new_authority);
+
+    Ok(())
+}
+
+pub fn process_accept_pool_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staking_pool_account = next_account_info(accounts_iter)?;
+    let new_authority = next_account_info(accounts_iter)?;
+
+    if !new_authority.is_signer {
+        msg!("New authority must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if staking_pool_account.owner != program_id {
+        msg!("Staking pool must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut pool_data = StakingPool::try_from_slice(&staking_pool_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if pool_data.pending_authority == Pubkey::default()
+        || pool_data.pending_authority != *new_authority.key
+    {
+        msg!("Signer is not the pending pool authority");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    pool_data.authority = *new_authority.key;
+    pool_data.pending_authority = Pubkey::default();
+    pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
+
+    msg!("Pool authority transferred to: {}", new_authority.key);
+
+    Ok(())
+}
+
+// Sweeps unused reward funding to the authority
+pub fn process_close_staking_pool(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staking_pool_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let reward_vault = next_account_info(accounts_iter)?;
+    let authority_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    let pool_data = load_pool_as_authority(program_id, staking_pool_account, authority)?;
+
+    if !pool_data.is_closable() {
+        msg!("Cannot close a pool that still holds stake, owed rewards or undistributed penalties");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if pool_data.reward_vault != *reward_vault.key {
+        msg!("Invalid reward vault");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let remaining_rewards = unpack_token_account(reward_vault)?.amount;
+    if remaining_rewards > 0 {
+        pool_data.transfer_from_vault(
+            staking_pool_account,
+            reward_vault,
+            authority_token_account,
+            token_program,
+            remaining_rewards,
+            pool_data.reward_decimals,
+        )?;
+        msg!("Unused rewards returned: {}", remaining_rewards);
+    }
+
+    // Close the pool account
+    let lamports = staking_pool_account.lamports();
+    **staking_pool_account.try_borrow_mut_lamports()? -= lamports;
+    **authority.try_borrow_mut_lamports()? += lamports;
+    staking_pool_account.data.borrow_mut().fill(0);
+
+    msg!("Staking pool closed");
+
+    Ok(())
+}
+
+// Penalty decays to zero at lock_end_time; with a cooldown the payout is queued
+pub fn process_early_unstake(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staking_pool_account = next_account_info(accounts_iter)?;
+    let user_stake_account = next_account_info(accounts_iter)?;
+    let user = next_account_info(accounts_iter)?;
+    let stake_vault = next_account_info(accounts_iter)?;
+    let user_token_account = next_account_info(accounts_iter)?;
+    let reward_vault = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if !user.is_signer {
+        msg!("User must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if staking_pool_account.owner != program_id {
+        msg!("Staking pool must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let args = UnstakeArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let clock = Clock::get()?;
+
+    // Load pool
+    let mut pool_data = StakingPool::try_from_slice(&staking_pool_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    pool_data.update_rewards(clock.unix_timestamp);
+
+    // Load stake
+    let mut stake_data = UserStake::try_from_slice(&user_stake_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if stake_data.user != *user.key {
+        msg!("Invalid user for stake account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_data.staking_pool != *staking_pool_account.key
+        || stake_data.pool_created_at != pool_data.created_at
+    {
+        msg!("Stake account does not belong to this pool");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if pool_data.stake_vault != *stake_vault.key {
+        msg!("Invalid stake vault");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if pool_data.reward_vault != *reward_vault.key {
+        msg!("Invalid reward vault");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if clock.unix_timestamp >= stake_data.lock_end_time {
+        msg!("Stake is not locked, use unstake instead");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if args.amount == 0 || args.amount > stake_data.amount {
+        msg!("Insufficient staked amount");
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    let penalty = stake_data.early_unstake_penalty(
+        args.amount,
+        pool_data.early_unstake_penalty_bps,
+        clock.unix_timestamp,
+    );
+    let payout = args.amount - penalty;
+
+    let cooling_down = pool_data.unstake_cooldown_seconds > 0 && payout > 0;
+    if cooling_down && stake_data.pending_withdrawals.len() >= UserStake::MAX_PENDING_WITHDRAWALS {
+        msg!("Too many pending withdrawals, withdraw matured ones first");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Update stake, forfeiting unpaid rewards
+    stake_data.settle_rewards(&mut pool_data);
+    let forfeited = stake_data.pending_rewards;
+    stake_data.pending_rewards = 0;
+    pool_data.total_pending_rewards = pool_data.total_pending_rewards.saturating_sub(forfeited);
+    stake_data.amount -= args.amount;
+    reweight_stake(&mut pool_data, &mut stake_data);
+
+    let available_at = clock
+        .unix_timestamp
+        .checked_add(pool_data.unstake_cooldown_seconds)
+        .ok_or(ProgramError::InvalidArgument)?;
+    if cooling_down {
+        stake_data.pending_withdrawals.push(PendingWithdrawal {
+            amount: payout,
+            available_at,
+        });
+        pool_data.total_unbonding = pool_data.total_unbonding.saturating_add(payout);
+    }
+    stake_data.serialize(&mut &mut user_stake_account.data.borrow_mut()[..])?;
+
+    // Update pool
+    pool_data.total_staked = pool_data.total_staked.saturating_sub(args.amount);
+
+    // Penalties fund future rewards when they share a mint, otherwise they stay
+    // in the stake vault and are credited to the remaining stakers
+    let redistribute_penalty = pool_data.reward_mint != pool_data.mint;
+    if redistribute_penalty {
+        pool_data.distribute_penalty(penalty);
+    }
+    pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
+
+    if payout > 0 && !cooling_down {
+        pool_data.transfer_from_vault(
+            staking_pool_account,
+            stake_vault,
+            user_token_account,
+            token_program,
+            payout,
+            6,
+        )?;
+    }
+
+    if penalty > 0 && !redistribute_penalty {
+        pool_data.transfer_from_vault(
+            staking_pool_account,
+            stake_vault,
+            reward_vault,
+            token_program,
+            penalty,
+            6,
+        )?;
+    }
+
+    msg!("Unstaked early: {}", args.amount);
+    msg!("Principal penalty: {}", penalty);
+    if cooling_down {
+        msg!("Payout available to withdraw at: {}", available_at);
+    }
+    msg!("Rewards forfeited: {}", forfeited);
+
+    Ok(())
+}
+
+pub fn process_set_early_unstake_penalty(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staking_pool_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+
+    let args = SetEarlyUnstakePenaltyArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    if args.early_unstake_penalty_bps > StakingPool::MAX_EARLY_UNSTAKE_PENALTY_BPS {
+        msg!("Penalty cannot exceed {} bps", StakingPool::MAX_EARLY_UNSTAKE_PENALTY_BPS);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut pool_data = load_pool_as_authority(program_id, staking_pool_account, authority)?;
+
+    pool_data.early_unstake_penalty_bps = args.early_unstake_penalty_bps;
+    pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
+
+    msg!("Early unstake penalty set to: {} bps", args.early_unstake_penalty_bps);
+
+    Ok(())
+}
+
+// Unbonding tokens earn no rewards
+pub fn process_request_unstake(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staking_pool_account = next_account_info(accounts_iter)?;
+    let user_stake_account = next_account_info(accounts_iter)?;
+    let user = next_account_info(accounts_iter)?;
+
+    if !user.is_signer {
+        msg!("User must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if staking_pool_account.owner != program_id {
+        msg!("Staking pool must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let args = UnstakeArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let clock = Clock::get()?;
+
+    // Load pool
+    let mut pool_data = StakingPool::try_from_slice(&staking_pool_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    pool_data.update_rewards(clock.unix_timestamp);
+
+    // Load stake
+    let mut stake_data = UserStake::try_from_slice(&user_stake_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if stake_data.user != *user.key {
+        msg!("Invalid user for stake account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_data.staking_pool != *staking_pool_account.key
+        || stake_data.pool_created_at != pool_data.created_at
+    {
+        msg!("Stake account does not belong to this pool");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if clock.unix_timestamp < stake_data.lock_end_time {
+        msg!("Stake is still locked");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if args.amount == 0 || args.amount > stake_data.amount {
+        msg!("Insufficient staked amount");
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    if stake_data.pending_withdrawals.len() >= UserStake::MAX_PENDING_WITHDRAWALS {
+        msg!("Too many pending withdrawals, withdraw matured ones first");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let available_at = clock
+        .unix_timestamp
+        .checked_add(pool_data.unstake_cooldown_seconds)
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    // Update stake, keeping rewards earned so far claimable
+    stake_data.settle_rewards(&mut pool_data);
+    stake_data.amount -= args.amount;
+    reweight_stake(&mut pool_data, &mut stake_data);
+    stake_data.pending_withdrawals.push(PendingWithdrawal {
+        amount: args.amount,
+        available_at,
+    });
+    stake_data.serialize(&mut &mut user_stake_account.data.borrow_mut()[..])?;
+
+    // Update pool
+    pool_data.total_staked = pool_data.total_staked.saturating_sub(args.amount);
+    pool_data.total_unbonding = pool_data.total_unbonding.saturating_add(args.amount);
+    pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
+
+    msg!("Unstake requested: {}", args.amount);
+    msg!("Available to withdraw at: {}", available_at);
+
+    Ok(())
+}
+
+pub fn process_withdraw_unstaked(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staking_pool_account = next_account_info(accounts_iter)?;
+    let user_stake_account = next_account_info(accounts_iter)?;
+    let user = next_account_info(accounts_iter)?;
+    let stake_vault = next_account_info(accounts_iter)?;
+    let user_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if !user.is_signer {
+        msg!("User must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if staking_pool_account.owner != program_id {
+        msg!("Staking pool must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let clock = Clock::get()?;
+
+    // Load pool
+    let mut pool_data = StakingPool::try_from_slice(&staking_pool_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    // Load stake
+    let mut stake_data = UserStake::try_from_slice(&user_stake_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if stake_data.user != *user.key {
+        msg!("Invalid user for stake account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_data.staking_pool != *staking_pool_account.key
+        || stake_data.pool_created_at != pool_data.created_at
+    {
+        msg!("Stake account does not belong to this pool");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if pool_data.stake_vault != *stake_vault.key {
+        msg!("Invalid stake vault");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut amount: u64 = 0;
+    stake_data.pending_withdrawals.retain(|withdrawal| {
+        if withdrawal.available_at <= clock.unix_timestamp {
+            amount = amount.saturating_add(withdrawal.amount);
+            false
+        } else {
+            true
+        }
+    });
+
+    if amount == 0 {
+        msg!("No withdrawals have finished their cooldown");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    pool_data.transfer_from_vault(
+        staking_pool_account,
+        stake_vault,
+        user_token_account,
+        token_program,
+        amount,
+        6,
+    )?;
+
+    stake_data.serialize(&mut &mut user_stake_account.data.borrow_mut()[..])?;
+
+    pool_data.total_unbonding = pool_data.total_unbonding.saturating_sub(amount);
+    pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
+
+    msg!("Withdrawn unstaked tokens: {}", amount);
+    msg!("Withdrawals still cooling down: {}", stake_data.pending_withdrawals.len());
+
+    Ok(())
+}
+
+pub fn process_set_unstake_cooldown(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staking_pool_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+
+    let args = SetUnstakeCooldownArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    if !StakingPool::is_valid_cooldown(args.unstake_cooldown_seconds) {
+        msg!("Cooldown must be between 0 and {} seconds", StakingPool::MAX_UNSTAKE_COOLDOWN_SECONDS);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut pool_data = load_pool_as_authority(program_id, staking_pool_account, authority)?;
+
+    // Applies to new requests; pending withdrawals keep their release time
+    pool_data.unstake_cooldown_seconds = args.unstake_cooldown_seconds;
+    pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
+
+    msg!("Unstake cooldown set to: {} seconds", args.unstake_cooldown_seconds);
+
+    Ok(())
+}
+
+// Existing positions keep their old weights until their next interaction
+pub fn process_set_tier_table(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staking_pool_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+
+    let args = SetTierTableArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    if !StakingPool::validate_tiers(&args.tiers) {
+        msg!("Tiers must be 1-{} entries, ascending by min amount, with APY at most {} bps",
+            StakingPool::MAX_TIERS, StakingPool::MAX_APY_BPS);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut pool_data = load_pool_as_authority(program_id, staking_pool_account, authority)?;
+
+    let clock = Clock::get()?;
+    pool_data.update_rewards(clock.unix_timestamp);
+
+    pool_data.tiers = args.tiers;
+    pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
+
+    emit_event(&TierTableUpdated {
+        staking_pool: *staking_pool_account.key,
+        tiers: pool_data.tiers.clone(),
+        updated_at: clock.unix_timestamp,
+    })?;
+
+    msg!("Tier table updated: {} tiers", pool_data.tiers.len());
+
+    Ok(())
+}
+
+// Existing positions keep their old weights until their next interaction
+pub fn process_set_lock_table(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staking_pool_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+
+    let args = SetLockTableArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    if !StakingPool::validate_lock_tiers(&args.lock_tiers) {
+        msg!("Lock tiers must be 1-{} durations of at most {} seconds, ascending, with multipliers of {}-{} bps",
+            StakingPool::MAX_LOCK_TIERS, StakingPool::MAX_LOCK_DURATION_SECONDS,
+            StakingPool::MIN_MULTIPLIER_BPS, StakingPool::MAX_MULTIPLIER_BPS);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut pool_data = load_pool_as_authority(program_id, staking_pool_account, authority)?;
+
+    let clock = Clock::get()?;
+    pool_data.update_rewards(clock.unix_timestamp);
+
+    pool_data.lock_tiers = args.lock_tiers;
+    pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
+
+    emit_event(&LockTableUpdated {
+        staking_pool: *staking_pool_account.key,
+        lock_tiers: pool_data.lock_tiers.clone(),
+        updated_at: clock.unix_timestamp,
+    })?;
+
+    msg!("Lock table updated: {} lock tiers", pool_data.lock_tiers.len());
+
+    Ok(())
+}
+
+// Lock end never moves earlier
+pub fn process_extend_lock(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staking_pool_account = next_account_info(accounts_iter)?;
+    let user_stake_account = next_account_info(accounts_iter)?;
+    let user = next_account_info(accounts_iter)?;
+
+    if !user.is_signer {
+        msg!("User must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if staking_pool_account.owner != program_id || user_stake_account.owner != program_id {
+        msg!("Staking accounts must be owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let args = ExtendLockArgs::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let clock = Clock::get()?;
+
+    // Load pool
+    let mut pool_data = StakingPool::try_from_slice(&staking_pool_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    pool_data.update_rewards(clock.unix_timestamp);
+
+    // Load stake
+    let mut stake_data = UserStake::try_from_slice(&user_stake_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if stake_data.user != *user.key {
+        msg!("Invalid user for stake account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_data.staking_pool != *staking_pool_account.key
+        || stake_data.pool_created_at != pool_data.created_at
+    {
+        msg!("Stake account does not belong to this pool");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if stake_data.amount == 0 {
+        msg!("Position has no active stake");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if args.lock_duration_seconds < stake_data.lock_duration_seconds {
+        msg!("Lock duration cannot be shortened");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if pool_data.lock_multiplier_bps(args.lock_duration_seconds).is_none() {
+        msg!("Lock duration is not offered by this pool");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Settle rewards earned at the old multiplier first
+    stake_data.settle_rewards(&mut pool_data);
+
+    let new_lock_end_time = stake_data
+        .lock_end_time
+        .max(clock.unix_timestamp + args.lock_duration_seconds);
+
+    stake_data.lock_duration_seconds = args.lock_duration_seconds;
+    stake_data.lock_end_time = new_lock_end_time;
+    reweight_stake(&mut pool_data, &mut stake_data);
+
+    stake_data.serialize(&mut &mut user_stake_account.data.borrow_mut()[..])?;
+    pool_data.serialize(&mut &mut staking_pool_account.data.borrow_mut()[..])?;
+
+    msg!("Lock extended");
+    msg!("Lock ends at: {}", stake_data.lock_end_time);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn pool() -> StakingPool {
+        StakingPool {
+            authority: Pubkey::new_unique(),
+            pending_authority: Pubkey::default(),
+            mint: Pubkey::new_unique(),
+            reward_mint: Pubkey::new_unique(),
+            reward_decimals: 6,
+            stake_vault: Pubkey::new_unique(),
+            reward_vault: Pubkey::new_unique(),
+            reward_rate_per_second: 0,
+            total_staked: 0,
+            max_total_staked: 0,
+            is_paused: false,
+            early_unstake_penalty_bps: 2000,
+            unstake_cooldown_seconds: 0,
+            total_unbonding: 0,
+            total_pending_rewards: 0,
+            tiers: StakingPool::default_tiers(),
+            lock_tiers: StakingPool::default_lock_tiers(),
+            total_weight: 0,
+            acc_reward_per_share: 0,
+            acc_penalty_per_share: 0,
+            undistributed_penalties: 0,
+            last_update_time: 0,
+            created_at: 0,
+            bump: 255,
+        }
+    }
+
+    fn stake(pool_data: &mut StakingPool, amount: u64, lock_period: LockPeriod) -> UserStake {
+        let mut stake_data = UserStake {
+            user: Pubkey::new_unique(),
+            staking_pool: Pubkey::new_unique(),
+            pool_created_at: pool_data.created_at,
+            position_index: 0,
+            amount,
+            lock_duration_seconds: lock_period.to_seconds(),
+            lock_end_time: lock_period.to_seconds(),
+            last_claim_time: 0,
+            total_claimed: 0,
+            weight: 0,
+            reward_debt: 0,
+            penalty_debt: 0,
+            pending_rewards: 0,
+            pending_withdrawals: Vec::new(),
+            created_at: 0,
+            bump: 255,
+        };
+        reweight_stake(pool_data, &mut stake_data);
+        pool_data.total_staked += amount;
+        stake_data
+    }
+
+    #[test]
+    fn full_accounts_fit_in_max_size() {
+        let mut pool_data = pool();
+        pool_data.tiers = vec![RewardTier { min_amount: u64::MAX, apy_bps: 0 }; StakingPool::MAX_TIERS];
+        pool_data.lock_tiers =
+            vec![LockTier { duration_seconds: 0, multiplier_bps: 0 }; StakingPool::MAX_LOCK_TIERS];
+        assert!(pool_data.try_to_vec().unwrap().len() <= StakingPool::MAX_SIZE);
+
+        let mut stake_data = stake(&mut pool_data, 1, LockPeriod::None);
+        for _ in 0..UserStake::MAX_PENDING_WITHDRAWALS {
+            stake_data.pending_withdrawals.push(PendingWithdrawal { amount: 1, available_at: 1 });
+        }
+        assert!(stake_data.try_to_vec().unwrap().len() <= UserStake::MAX_SIZE);
+    }
+
+    #[test]
+    fn lock_tables_accept_custom_durations() {
+        assert!(StakingPool::validate_lock_tiers(&StakingPool::default_lock_tiers()));
+        assert!(StakingPool::validate_lock_tiers(&[
+            LockTier { duration_seconds: 0, multiplier_bps: 10000 },
+            LockTier { duration_seconds: 45 * DAY, multiplier_bps: 15000 },
+        ]));
+
+        // Unsorted, negative, too long or out-of-range multipliers are rejected
+        assert!(!StakingPool::validate_lock_tiers(&[]));
+        assert!(!StakingPool::validate_lock_tiers(&[
+            LockTier { duration_seconds: 45 * DAY, multiplier_bps: 15000 },
+            LockTier { duration_seconds: 0, multiplier_bps: 10000 },
+        ]));
+        assert!(!StakingPool::validate_lock_tiers(&[LockTier { duration_seconds: -1, multiplier_bps: 10000 }]));
+        assert!(!StakingPool::validate_lock_tiers(&[LockTier {
+            duration_seconds: StakingPool::MAX_LOCK_DURATION_SECONDS + 1,
+            multiplier_bps: 10000,
+        }]));
+        assert!(!StakingPool::validate_lock_tiers(&[LockTier { duration_seconds: 0, multiplier_bps: 9999 }]));
+    }
+
+    #[test]
+    fn promo_durations_earn_their_multiplier() {
+        let mut pool_data = pool();
+        pool_data.lock_tiers.push(LockTier { duration_seconds: 365 * DAY, multiplier_bps: 30000 });
+
+        // Silver tier (15%) at 3.0x
+        assert_eq!(pool_data.calculate_tier_apy(1_000_000_000, 365 * DAY), 4500);
+        // Durations missing from the table earn no boost
+        assert_eq!(pool_data.calculate_tier_apy(1_000_000_000, 7 * DAY), 1500);
+    }
+
+    #[test]
+    fn early_unstake_penalty_decays_to_zero_at_lock_end() {
+        let mut pool_data = pool();
+        let stake_data = stake(&mut pool_data, 1_000_000_000, LockPeriod::NinetyDays);
+        let lock_end = stake_data.lock_end_time;
+
+        assert_eq!(stake_data.early_unstake_penalty(1_000_000, 2000, 0), 200_000);
+        assert_eq!(stake_data.early_unstake_penalty(1_000_000, 2000, lock_end / 2), 100_000);
+        assert_eq!(stake_data.early_unstake_penalty(1_000_000, 2000, lock_end - DAY), 2_222);
+        assert_eq!(stake_data.early_unstake_penalty(1_000_000, 2000, lock_end), 0);
+        assert_eq!(stake_data.early_unstake_penalty(1_000_000, 2000, lock_end + DAY), 0);
+    }
+
+    #[test]
+    fn early_unstake_penalty_is_capped_at_the_full_rate() {
+        let mut pool_data = pool();
+        let mut stake_data = stake(&mut pool_data, 1_000_000_000, LockPeriod::ThirtyDays);
+
+        // A lock end pushed past one lock duration still charges at most the full rate
+        stake_data.lock_end_time += 10 * DAY;
+        assert_eq!(stake_data.early_unstake_penalty(1_000_000, 2000, 0), 200_000);
+        assert_eq!(stake_data.early_unstake_penalty(u64::MAX, 5000, 0), u64::MAX / 2);
+    }
+
+    #[test]
+    fn unlocked_stakes_pay_no_penalty() {
+        let mut pool_data = pool();
+        let stake_data = stake(&mut pool_data, 1_000_000_000, LockPeriod::None);
+        assert_eq!(stake_data.early_unstake_penalty(1_000_000, 5000, -DAY), 0);
+    }
+
+    #[test]
+    fn penalties_are_credited_to_remaining_stakers_by_weight() {
+        let mut pool_data = pool();
+        let mut small = stake(&mut pool_data, 1_000_000_000, LockPeriod::None);
+        let mut large = stake(&mut pool_data, 3_000_000_000, LockPeriod::None);
+
+        pool_data.distribute_penalty(4_000_000);
+        small.settle_rewards(&mut pool_data);
+        large.settle_rewards(&mut pool_data);
+
+        // Shares round down, leaving dust in the vault
+        let small_share = small.amount - 1_000_000_000;
+        let large_share = large.amount - 3_000_000_000;
+        assert!((999_999..=1_000_000).contains(&small_share));
+        assert!((2_999_999..=3_000_000).contains(&large_share));
+        assert_eq!(pool_data.total_staked, 4_000_000_000 + small_share + large_share);
+
+        // Settling again credits nothing new
+        let amount = small.amount;
+        small.settle_rewards(&mut pool_data);
+        assert_eq!(small.amount, amount);
+    }
+
+    #[test]
+    fn pool_with_undistributed_penalties_cannot_close() {
+        let mut pool_data = pool();
+        let mut last = stake(&mut pool_data, 1_000_000_000, LockPeriod::NinetyDays);
+        assert!(!pool_data.is_closable());
+
+        // The last staker leaves early, so nobody is left to receive the penalty
+        let penalty = last.early_unstake_penalty(last.amount, pool_data.early_unstake_penalty_bps, 0);
+        last.settle_rewards(&mut pool_data);
+        pool_data.total_staked -= last.amount;
+        last.amount = 0;
+        reweight_stake(&mut pool_data, &mut last);
+        pool_data.distribute_penalty(penalty);
+
+        assert_eq!(pool_data.total_staked, 0);
+        assert_eq!(pool_data.undistributed_penalties, penalty);
+        assert!(!pool_data.is_closable());
+    }
+
+    #[test]
+    fn penalty_credits_raise_the_stake_weight() {
+        let mut pool_data = pool();
+        let mut staker = stake(&mut pool_data, 1_000_000_000, LockPeriod::None);
+        let other = stake(&mut pool_data, 1_000_000_000, LockPeriod::None);
+        let weight = staker.weight;
+
+        pool_data.distribute_penalty(200_000_000);
+        staker.settle_rewards(&mut pool_data);
+
+        assert!(staker.weight > weight);
+        assert_eq!(staker.weight, pool_data.reward_weight(staker.amount, staker.lock_duration_seconds));
+        assert_eq!(pool_data.total_weight, staker.weight + other.weight);
+    }
+
+    #[test]
+    fn penalties_wait_for_stakers_when_nobody_has_weight() {
+        let mut pool_data = pool();
+        pool_data.distribute_penalty(500);
+        assert_eq!(pool_data.undistributed_penalties, 500);
+        assert_eq!(pool_data.acc_penalty_per_share, 0);
+
+        let mut stake_data = stake(&mut pool_data, 1_000_000_000, LockPeriod::None);
+        pool_data.update_rewards(1);
+        stake_data.settle_rewards(&mut pool_data);
+
+        assert_eq!(pool_data.undistributed_penalties, 0);
+        assert!((499..=500).contains(&(stake_data.amount - 1_000_000_000)));
+    }
+}